@@ -0,0 +1,51 @@
+//! Measures the cost of running a voice (glottis and tract) in real time.
+//!
+//! Run with `cargo run --release --example tract_cost`.
+
+use core::filter::stateful::tract::TractBuilder;
+use core::source::Glottis;
+use core::Float;
+use std::time::Instant;
+
+const SAMPLE_RATE: f64 = 44100.0;
+const BLOCK_SIZE: usize = 64;
+const SECONDS: usize = 10;
+
+/// Renders `SECONDS` of audio and returns the fraction of real time taken.
+fn measure<T: Float>() -> f64 {
+    let mut glottis = Glottis::<T>::new();
+    glottis.set_sample_rate(SAMPLE_RATE);
+    let mut tract = TractBuilder::new()
+        .oral_length(44)
+        .nasal_length(28)
+        .sample_rate(SAMPLE_RATE)
+        .build::<T>()
+        .expect("invalid tract configuration");
+    tract.init();
+
+    let mut buffer = [T::zero(); BLOCK_SIZE];
+    let mut opening = [0.0; BLOCK_SIZE];
+    let mut sum = T::zero();
+    let start = Instant::now();
+    for _ in 0..SECONDS * SAMPLE_RATE as usize / BLOCK_SIZE {
+        glottis.process_block_with_opening(&mut buffer, &mut opening);
+        tract.process_block_with(&mut buffer, None, Some(&opening));
+        sum += buffer[BLOCK_SIZE - 1];
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+
+    // keep the result alive so the render isn't optimised away
+    assert!(sum.is_finite());
+    elapsed / SECONDS as f64
+}
+
+fn main() {
+    for (name, cost) in [("f32", measure::<f32>()), ("f64", measure::<f64>())] {
+        println!(
+            "{}: {:.3}% of a core per voice ({:.0} voices in real time)",
+            name,
+            cost * 100.0,
+            1.0 / cost
+        );
+    }
+}
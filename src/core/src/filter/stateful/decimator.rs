@@ -0,0 +1,279 @@
+//! Decimation of oversampled signals.
+
+use crate::filter::stateless::blackman_window;
+use crate::float::Float;
+use std::f64::consts::PI;
+
+/// The number of taps of each half-band filter (of the form 4k + 3, so the outer taps are
+/// non-zero).
+const HALF_BAND_TAPS: usize = 35;
+/// The largest supported oversampling factor.
+pub const MAX_OVERSAMPLING: usize = 4;
+
+/// The factor by which a signal is oversampled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Oversampling {
+    X1,
+    #[default]
+    X2,
+    X4,
+}
+
+impl Oversampling {
+    /// Returns the number of samples per output sample.
+    pub fn factor(self) -> usize {
+        match self {
+            Oversampling::X1 => 1,
+            Oversampling::X2 => 2,
+            Oversampling::X4 => 4,
+        }
+    }
+}
+
+/// A delay line whose contents can be read in order without shifting them.
+#[derive(Clone, Debug)]
+pub(crate) struct History<T: Float> {
+    /// The delayed samples, stored twice so they are always contiguous.
+    buffer: Vec<T>,
+    /// The index of the newest sample.
+    position: usize,
+}
+
+impl<T: Float> History<T> {
+    pub(crate) fn new(length: usize) -> History<T> {
+        History {
+            buffer: vec![T::zero(); 2 * length],
+            position: 0,
+        }
+    }
+
+    pub(crate) fn push(&mut self, value: T) {
+        let length = self.buffer.len() / 2;
+        self.position = (self.position + 1) % length;
+        self.buffer[self.position] = value;
+        self.buffer[self.position + length] = value;
+    }
+
+    /// Returns the delayed samples from oldest to newest.
+    pub(crate) fn samples(&self) -> &[T] {
+        let length = self.buffer.len() / 2;
+        &self.buffer[self.position + 1..][..length]
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.buffer
+            .iter_mut()
+            .for_each(|sample| *sample = T::zero());
+    }
+}
+
+/// A polyphase half-band low-pass filter, halving the sample rate of a signal.
+///
+/// Every other tap of a half-band filter is zero apart from its centre, so the filter splits
+/// into a symmetric branch over odd samples and a pure delay over even samples, and only the
+/// retained outputs are computed.
+#[derive(Clone, Debug)]
+pub struct HalfBand<T: Float = f64> {
+    /// The non-zero taps applied to odd samples (symmetric).
+    coefficients: Vec<T>,
+    odd: History<T>,
+    even: History<T>,
+}
+
+impl<T: Float> Default for HalfBand<T> {
+    fn default() -> HalfBand<T> {
+        HalfBand::new()
+    }
+}
+
+impl<T: Float> HalfBand<T> {
+    /// Creates a windowed-sinc half-band filter.
+    pub fn new() -> HalfBand<T> {
+        let centre = (HALF_BAND_TAPS - 1) as f64 / 2.0;
+        let window = blackman_window(HALF_BAND_TAPS);
+        let taps: Vec<f64> = (0..HALF_BAND_TAPS)
+            .step_by(2)
+            .map(|i| {
+                let x = PI * (i as f64 - centre) / 2.0;
+                window[i] * x.sin() / x
+            })
+            .collect();
+
+        // the centre tap passes half of the signal, so the odd branch passes the other half
+        let sum: f64 = taps.iter().sum();
+        HalfBand {
+            coefficients: taps
+                .iter()
+                .map(|&tap| T::from_f64(0.5 * tap / sum))
+                .collect(),
+            odd: History::new(taps.len()),
+            even: History::new((HALF_BAND_TAPS + 1) / 4),
+        }
+    }
+
+    /// Filters a pair of consecutive samples, returning a single sample at half the rate.
+    pub fn process(&mut self, even: T, odd: T) -> T {
+        self.even.push(even);
+        self.odd.push(odd);
+        let delayed = self.even.samples()[0] * T::from_f64(0.5);
+        self.coefficients
+            .iter()
+            .zip(self.odd.samples())
+            .fold(delayed, |sum, (&coefficient, &sample)| {
+                sum + coefficient * sample
+            })
+    }
+
+    /// Returns the delay of the filter in samples at the output rate.
+    pub fn latency(&self) -> f64 {
+        (HALF_BAND_TAPS - 1) as f64 / 4.0
+    }
+
+    /// Silences the filter.
+    pub fn reset(&mut self) {
+        self.odd.reset();
+        self.even.reset();
+    }
+}
+
+/// Reduces an oversampled signal to its output rate through a cascade of half-band filters,
+/// so content above the output's Nyquist frequency is removed rather than aliased.
+#[derive(Clone, Debug)]
+pub struct Decimator<T: Float = f64> {
+    oversampling: Oversampling,
+    stages: Vec<HalfBand<T>>,
+}
+
+impl<T: Float> Decimator<T> {
+    pub fn new(oversampling: Oversampling) -> Decimator<T> {
+        let stages = oversampling.factor().trailing_zeros() as usize;
+        Decimator {
+            oversampling,
+            stages: (0..stages).map(|_| HalfBand::new()).collect(),
+        }
+    }
+
+    pub fn oversampling(&self) -> Oversampling {
+        self.oversampling
+    }
+
+    /// Decimates one output sample's worth of oversampled input.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `input` does not hold exactly [`Oversampling::factor`] samples.
+    pub fn process(&mut self, input: &[T]) -> T {
+        let mut length = self.oversampling.factor();
+        assert_eq!(input.len(), length, "wrong number of oversampled samples");
+
+        let mut buffer = [T::zero(); MAX_OVERSAMPLING];
+        buffer[..length].copy_from_slice(input);
+        for stage in &mut self.stages {
+            length /= 2;
+            for i in 0..length {
+                buffer[i] = stage.process(buffer[2 * i], buffer[2 * i + 1]);
+            }
+        }
+        buffer[0]
+    }
+
+    /// Returns the delay of the decimator in samples at the output rate.
+    pub fn latency(&self) -> f64 {
+        // each stage runs at twice the rate of the next
+        let mut scale = 1.0;
+        self.stages
+            .iter()
+            .rev()
+            .map(|stage| {
+                let latency = stage.latency() * scale;
+                scale *= 0.5;
+                latency
+            })
+            .sum()
+    }
+
+    /// Silences the decimator.
+    pub fn reset(&mut self) {
+        self.stages.iter_mut().for_each(HalfBand::reset);
+    }
+
+    /// Returns the delayed samples of every stage, for snapshots.
+    pub(crate) fn state(&self) -> Vec<f64> {
+        self.stages
+            .iter()
+            .flat_map(|stage| stage.odd.samples().iter().chain(stage.even.samples()))
+            .map(|sample| sample.as_f64())
+            .collect()
+    }
+
+    /// Restores the delayed samples of every stage from [`Decimator::state`], returning
+    /// whether the state has the expected length.
+    pub(crate) fn set_state(&mut self, state: &[f64]) -> bool {
+        if state.len() != self.state().len() {
+            return false;
+        }
+        let mut values = state.iter().map(|&value| T::from_f64(value));
+        for stage in &mut self.stages {
+            stage.reset();
+            for history in [&mut stage.odd, &mut stage.even] {
+                let length = history.buffer.len() / 2;
+                for _ in 0..length {
+                    history.push(values.next().unwrap());
+                }
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the amplitude of a decimator's output for a sine at a fraction of the input rate.
+    fn amplitude(oversampling: Oversampling, frequency: f64) -> f64 {
+        let mut decimator: Decimator<f64> = Decimator::new(oversampling);
+        let factor = oversampling.factor();
+        let mut input = [0.0; MAX_OVERSAMPLING];
+        (0..2000)
+            .map(|n| {
+                for (i, sample) in input[..factor].iter_mut().enumerate() {
+                    *sample = (2.0 * PI * frequency * (n * factor + i) as f64).sin();
+                }
+                decimator.process(&input[..factor])
+            })
+            .skip(100)
+            .map(|sample: f64| sample * sample * 2.0 / 1900.0)
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    #[test]
+    fn test_decimation() {
+        for oversampling in [Oversampling::X2, Oversampling::X4] {
+            let factor = oversampling.factor() as f64;
+            // a tenth of the output rate passes, whereas 0.4 of the input rate would alias
+            assert!((amplitude(oversampling, 0.1 / factor) - 1.0).abs() < 1e-2);
+            assert!(amplitude(oversampling, 0.4) < 1e-3);
+        }
+        assert!((amplitude(Oversampling::X1, 0.4) - 1.0).abs() < 1e-2);
+
+        let decimator: Decimator = Decimator::new(Oversampling::X4);
+        assert!((decimator.latency() - 12.75).abs() < 1e-9);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_state() {
+        let mut decimator: Decimator = Decimator::new(Oversampling::X4);
+        for n in 0..50 {
+            decimator.process(&[n as f64, 1.0, -1.0, 0.5]);
+        }
+        let mut copy: Decimator = Decimator::new(Oversampling::X4);
+        assert!(copy.set_state(&decimator.state()));
+        assert!(!copy.set_state(&[0.0]));
+        let input = [0.3, 0.1, 0.0, -0.2];
+        assert_eq!(copy.process(&input), decimator.process(&input));
+    }
+}
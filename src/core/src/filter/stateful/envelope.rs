@@ -0,0 +1,345 @@
+//! Stateful envelope generators, for shaping any parameter over the course of a note.
+
+use crate::float::Float;
+use std::f64::consts::PI;
+
+/// The shape with which a segment moves from its starting level to its target.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Curve {
+    /// A constant rate of change.
+    Linear,
+    /// An exponential approach, fast at first for positive curvature and slow at first for
+    /// negative curvature.
+    Exponential(f64),
+    /// A raised-cosine ease in and out.
+    Smooth,
+}
+
+impl Curve {
+    /// Returns the progress of the curve in range [0-1] at point x in range [0-1].
+    pub fn shape(self, x: f64) -> f64 {
+        let x = x.clamp(0.0, 1.0);
+        match self {
+            Curve::Linear => x,
+            Curve::Exponential(curvature) if curvature.abs() > 1e-6 => {
+                (1.0 - (-curvature * x).exp()) / (1.0 - (-curvature).exp())
+            }
+            Curve::Exponential(_) => x,
+            Curve::Smooth => 0.5 * (1.0 - (PI * x).cos()),
+        }
+    }
+}
+
+/// A single stage of an [`Envelope`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Segment {
+    /// The level reached by the end of the segment.
+    pub target: f64,
+    /// The duration of the segment in seconds.
+    pub duration: f64,
+    /// The shape of the segment.
+    pub curve: Curve,
+}
+
+impl Segment {
+    pub fn new(target: f64, duration: f64, curve: Curve) -> Segment {
+        Segment {
+            target,
+            duration: duration.max(0.0),
+            curve,
+        }
+    }
+}
+
+/// How an envelope responds to a note starting while it is still sounding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Retrigger {
+    /// Restart from zero.
+    Reset,
+    /// Restart the first segment from the current level.
+    Continue,
+    /// Ignore the new note while the previous one is held, otherwise restart from the current
+    /// level.
+    Legato,
+}
+
+/// The stage of an envelope.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Stage {
+    /// Finished (or never started).
+    Idle,
+    /// Moving through a segment.
+    Segment(usize),
+    /// Holding at the end of the sustain segment until released.
+    Sustain,
+}
+
+/// A stateful multi-segment envelope.
+///
+/// Segments are followed in turn from the start of a note. If a sustain segment is given, its
+/// target is held until the note is released, whereupon the envelope moves through the
+/// remaining (release) segments from its current level.
+#[derive(Clone, Debug)]
+pub struct Envelope {
+    segments: Vec<Segment>,
+    /// The index of the segment at the end of which the envelope holds while the note is held.
+    sustain: Option<usize>,
+    retrigger: Retrigger,
+    sample_rate: f64,
+    stage: Stage,
+    /// The time elapsed within the current segment in seconds.
+    elapsed: f64,
+    /// The level at the start of the current segment.
+    start: f64,
+    level: f64,
+    gate: bool,
+}
+
+impl Envelope {
+    /// Creates an idle envelope from its segments and the index of its sustain segment.
+    pub fn new(segments: Vec<Segment>, sustain: Option<usize>, sample_rate: f64) -> Envelope {
+        Envelope {
+            sustain: sustain.filter(|&index| index < segments.len()),
+            segments,
+            retrigger: Retrigger::Continue,
+            sample_rate,
+            stage: Stage::Idle,
+            elapsed: 0.0,
+            start: 0.0,
+            level: 0.0,
+            gate: false,
+        }
+    }
+
+    /// Sets how the envelope responds to a note starting while it is still sounding.
+    pub fn set_retrigger(&mut self, retrigger: Retrigger) {
+        self.retrigger = retrigger;
+    }
+
+    /// Sets the sample rate at which [`Envelope::tick`] advances.
+    pub fn set_sample_rate(&mut self, sample_rate: f64) {
+        self.sample_rate = sample_rate;
+    }
+
+    /// Starts the envelope from the beginning of its first segment.
+    pub fn note_on(&mut self) {
+        if self.retrigger == Retrigger::Legato && self.gate {
+            return;
+        }
+        if self.retrigger == Retrigger::Reset {
+            self.level = 0.0;
+        }
+        self.gate = true;
+        self.enter(0);
+    }
+
+    /// Releases the envelope, moving to the segments after its sustain segment.
+    pub fn note_off(&mut self) {
+        if !self.gate {
+            return;
+        }
+        self.gate = false;
+        if let (Some(sustain), Stage::Segment(_) | Stage::Sustain) = (self.sustain, self.stage) {
+            self.enter(sustain + 1);
+        }
+    }
+
+    /// Silences the envelope immediately.
+    pub fn reset(&mut self) {
+        self.gate = false;
+        self.stage = Stage::Idle;
+        self.level = 0.0;
+    }
+
+    /// Returns the current level of the envelope.
+    pub fn level(&self) -> f64 {
+        self.level
+    }
+
+    /// Returns whether the envelope's note is held.
+    pub fn is_held(&self) -> bool {
+        self.gate
+    }
+
+    /// Returns whether the envelope is moving or sustaining.
+    pub fn is_active(&self) -> bool {
+        self.stage != Stage::Idle
+    }
+
+    /// Advances the envelope by a sample, returning its new level.
+    pub fn tick(&mut self) -> f64 {
+        self.advance(1.0 / self.sample_rate)
+    }
+
+    /// Advances the envelope by `delta_time` seconds, returning its new level.
+    pub fn advance(&mut self, delta_time: f64) -> f64 {
+        let mut remaining = delta_time;
+        while let Stage::Segment(index) = self.stage {
+            let segment = self.segments[index];
+            let left = segment.duration - self.elapsed;
+            if remaining < left {
+                self.elapsed += remaining;
+                let x = self.elapsed / segment.duration;
+                self.level = self.start + (segment.target - self.start) * segment.curve.shape(x);
+                break;
+            }
+
+            remaining -= left;
+            self.level = segment.target;
+            if self.gate && self.sustain == Some(index) {
+                self.stage = Stage::Sustain;
+            } else {
+                self.enter(index + 1);
+            }
+        }
+        self.level
+    }
+
+    /// Fills a block with the envelope's level at each sample.
+    pub fn process_block<T: Float>(&mut self, output: &mut [T]) {
+        for sample in output.iter_mut() {
+            *sample = T::from_f64(self.tick());
+        }
+    }
+
+    /// Moves to the segment at `index` from the current level, finishing if there is none.
+    fn enter(&mut self, index: usize) {
+        self.elapsed = 0.0;
+        self.start = self.level;
+        self.stage = if index < self.segments.len() {
+            Stage::Segment(index)
+        } else {
+            Stage::Idle
+        };
+    }
+}
+
+/// The parameters of an attack, decay, sustain and release envelope.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Adsr {
+    /// The time taken to rise to full level, in seconds.
+    pub attack: f64,
+    /// The time taken to fall to the sustain level, in seconds.
+    pub decay: f64,
+    /// The level held while the note is held.
+    pub sustain: f64,
+    /// The time taken to fall silent once released, in seconds.
+    pub release: f64,
+    /// The shape of the attack.
+    pub attack_curve: Curve,
+    /// The shape of the decay and release.
+    pub release_curve: Curve,
+}
+
+impl Adsr {
+    /// Creates an envelope following these parameters.
+    pub fn envelope(&self, sample_rate: f64) -> Envelope {
+        let segments = vec![
+            Segment::new(1.0, self.attack, self.attack_curve),
+            Segment::new(self.sustain, self.decay, self.release_curve),
+            Segment::new(0.0, self.release, self.release_curve),
+        ];
+        Envelope::new(segments, Some(1), sample_rate)
+    }
+}
+
+impl Default for Adsr {
+    fn default() -> Adsr {
+        Adsr {
+            attack: 0.01,
+            decay: 0.1,
+            sustain: 0.8,
+            release: 0.2,
+            attack_curve: Curve::Linear,
+            release_curve: Curve::Exponential(4.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adsr() -> Envelope {
+        Adsr {
+            attack: 0.1,
+            decay: 0.1,
+            sustain: 0.5,
+            release: 0.2,
+            attack_curve: Curve::Linear,
+            release_curve: Curve::Linear,
+        }
+        .envelope(1000.0)
+    }
+
+    #[test]
+    fn test_adsr() {
+        let mut envelope = adsr();
+        assert_eq!(envelope.tick(), 0.0);
+        assert!(!envelope.is_active());
+
+        envelope.note_on();
+        assert!((envelope.advance(0.05) - 0.5).abs() < 1e-9);
+        assert!((envelope.advance(0.1) - 0.75).abs() < 1e-9);
+        assert!((envelope.advance(1.0) - 0.5).abs() < 1e-9);
+        assert!(envelope.is_active());
+
+        envelope.note_off();
+        assert!((envelope.advance(0.1) - 0.25).abs() < 1e-9);
+        assert_eq!(envelope.advance(0.2), 0.0);
+        assert!(!envelope.is_active());
+    }
+
+    #[test]
+    fn test_release_before_sustain() {
+        let mut envelope = adsr();
+        envelope.note_on();
+        envelope.advance(0.05);
+        envelope.note_off();
+
+        // the release starts from the level reached
+        assert!((envelope.advance(0.1) - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_retrigger() {
+        let mut envelope = adsr();
+        envelope.note_on();
+        envelope.advance(1.0);
+
+        envelope.set_retrigger(Retrigger::Legato);
+        envelope.note_on();
+        assert!((envelope.advance(0.01) - 0.5).abs() < 1e-9);
+
+        envelope.set_retrigger(Retrigger::Continue);
+        envelope.note_on();
+        assert!((envelope.advance(0.05) - 0.75).abs() < 1e-9);
+
+        envelope.set_retrigger(Retrigger::Reset);
+        envelope.note_on();
+        assert!((envelope.advance(0.05) - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_multi_segment() {
+        let segments = vec![
+            Segment::new(1.0, 0.1, Curve::Exponential(3.0)),
+            Segment::new(0.2, 0.1, Curve::Smooth),
+            Segment::new(0.6, 0.0, Curve::Linear),
+        ];
+        let mut envelope = Envelope::new(segments, None, 100.0);
+        envelope.note_on();
+
+        // fast-starting curves lead a linear ramp
+        assert!(envelope.advance(0.05) > 0.5);
+        assert!((envelope.advance(0.2) - 0.6).abs() < 1e-9);
+        let mut block = [0.0f32; 10];
+        envelope.process_block(&mut block);
+        assert!(block.iter().all(|&level| (level - 0.6).abs() < 1e-6));
+        assert!(!envelope.is_active());
+    }
+}
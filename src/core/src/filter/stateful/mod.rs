@@ -1,13 +1,22 @@
 //! A collection of stateful signal filters.
 
+pub mod decimator;
+pub mod envelope;
+pub mod resampler;
 pub mod tract;
 
+use crate::float::Float;
+
+pub use decimator::{Decimator, HalfBand, Oversampling};
+pub use envelope::{Adsr, Curve, Envelope, Retrigger, Segment};
+pub use resampler::{resample, Resampler};
+
 /// A stateful delay line. Samples are delayed for `delay_length` seconds.
 ///
 /// https://en.wikipedia.org/wiki/Analog_delay_line
 ///
 /// ```
-/// use synthrs::filter::AllPass;
+/// use core::filter::stateful::AllPass;
 ///
 /// let mut allpass = AllPass::new(1.0, 44_100, 0.5);
 /// let samples: Vec<f64> = vec![1.0, 2.0, 3.0, 4.0];
@@ -17,21 +26,21 @@ pub mod tract;
 ///
 /// Taken from: https://github.com/irh/freeverb-rs/blob/master/freeverb/src/delay_line.rs
 #[derive(Clone, Debug)]
-pub struct DelayLine {
-    pub buf: Vec<f64>,
+pub struct DelayLine<T: Float = f64> {
+    pub buf: Vec<T>,
     index: usize,
     pub delay_length: f64,
     pub delay_samples: usize,
     pub sample_rate: usize,
 }
 
-impl DelayLine {
+impl<T: Float> DelayLine<T> {
     /// Creates a new delay line. Samples are delayed for `delay_length` seconds.
-    pub fn new(delay_length: f64, sample_rate: usize) -> DelayLine {
+    pub fn new(delay_length: f64, sample_rate: usize) -> DelayLine<T> {
         let delay_samples = ((delay_length * sample_rate as f64).round() + 1.0) as usize;
 
         DelayLine {
-            buf: vec![0.0; delay_samples],
+            buf: vec![T::zero(); delay_samples],
             index: 0,
             delay_length,
             delay_samples,
@@ -39,11 +48,11 @@ impl DelayLine {
         }
     }
 
-    pub fn read(&self) -> f64 {
+    pub fn read(&self) -> T {
         self.buf[self.index]
     }
 
-    pub fn write(&mut self, value: f64) {
+    pub fn write(&mut self, value: T) {
         self.buf[self.index] = value;
 
         if self.index == self.buf.len() - 1 {
@@ -59,7 +68,7 @@ impl DelayLine {
 /// https://en.wikipedia.org/wiki/All-pass_filter
 ///
 /// ```
-/// use synthrs::filter::AllPass;
+/// use core::filter::stateful::AllPass;
 ///
 /// let mut allpass = AllPass::new(1.0, 44_100, 0.5);
 /// let samples: Vec<f64> = vec![1.0, 2.0, 3.0, 4.0];
@@ -69,22 +78,22 @@ impl DelayLine {
 ///
 /// Taken from: https://github.com/irh/freeverb-rs/blob/master/freeverb/src/all_pass.rs
 #[derive(Clone, Debug)]
-pub struct AllPass {
-    delay_line: DelayLine,
+pub struct AllPass<T: Float = f64> {
+    delay_line: DelayLine<T>,
     /// Feedback multiplier (0.5 works)
-    pub feedback: f64,
+    pub feedback: T,
 }
 
-impl AllPass {
+impl<T: Float> AllPass<T> {
     /// Creates a new all-pass filter. Samples are delayed for `delay_length` seconds.
-    pub fn new(delay_length: f64, sample_rate: usize, feedback: f64) -> AllPass {
+    pub fn new(delay_length: f64, sample_rate: usize, feedback: T) -> AllPass<T> {
         AllPass {
             delay_line: DelayLine::new(delay_length, sample_rate),
             feedback,
         }
     }
 
-    pub fn tick(&mut self, input: f64) -> f64 {
+    pub fn tick(&mut self, input: T) -> T {
         let delayed = self.delay_line.read();
         self.delay_line.write(input + delayed * self.feedback);
         -input + delayed
@@ -96,7 +105,7 @@ impl AllPass {
 /// https://en.wikipedia.org/wiki/Comb_filter
 ///
 /// ```
-/// use synthrs::filter::Comb;
+/// use core::filter::stateful::Comb;
 ///
 /// let mut comb = Comb::new(1.0, 44_100, 0.5, 0.5, 0.5);
 /// let samples: Vec<f64> = vec![1.0, 2.0, 3.0, 4.0];
@@ -106,36 +115,36 @@ impl AllPass {
 ///
 /// Taken from: https://github.com/irh/freeverb-rs/blob/master/freeverb/src/comb.rs
 #[derive(Clone, Debug)]
-pub struct Comb {
-    delay_line: DelayLine,
-    filter_state: f64,
+pub struct Comb<T: Float = f64> {
+    delay_line: DelayLine<T>,
+    filter_state: T,
     /// 0.5 works
-    pub dampening_inverse: f64,
+    pub dampening_inverse: T,
     /// 0.5 works
-    pub dampening: f64,
+    pub dampening: T,
     /// 0.5 works
-    pub feedback: f64,
+    pub feedback: T,
 }
 
-impl Comb {
+impl<T: Float> Comb<T> {
     /// Creates a new comb filter. Samples are delayed for `delay_length` seconds.
     pub fn new(
         delay_length: f64,
         sample_rate: usize,
-        dampening_inverse: f64,
-        dampening: f64,
-        feedback: f64,
-    ) -> Comb {
+        dampening_inverse: T,
+        dampening: T,
+        feedback: T,
+    ) -> Comb<T> {
         Comb {
             dampening_inverse,
             dampening,
             delay_line: DelayLine::new(delay_length, sample_rate),
             feedback,
-            filter_state: 0.0,
+            filter_state: T::zero(),
         }
     }
 
-    pub fn tick(&mut self, input: f64) -> f64 {
+    pub fn tick(&mut self, input: T) -> T {
         let output = self.delay_line.read();
         self.filter_state = output * self.dampening_inverse + self.filter_state * self.dampening;
         self.delay_line
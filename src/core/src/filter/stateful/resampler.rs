@@ -0,0 +1,220 @@
+//! Band-limited sample rate conversion.
+
+use super::decimator::History;
+use crate::filter::stateless::lowpass_filter;
+use crate::float::Float;
+
+/// The number of input samples weighed for each output sample when upsampling (more are
+/// weighed when downsampling, so the transition band stays the same width).
+const TAPS: usize = 64;
+/// The most phases of the filter table (between which ratios with more are interpolated).
+const MAX_PHASES: u64 = 512;
+/// The resolution of the position of outputs between inputs at non-integer sample rates.
+const FRACTIONAL_RESOLUTION: u64 = 1 << 20;
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// A windowed-sinc polyphase sample rate converter.
+///
+/// Output samples lie at fixed phases between input samples, each weighing the surrounding
+/// inputs by one row of a table of low-pass filters. Integer rates convert exactly by their
+/// ratio, and other rates are approximated. Content above the lower of the two Nyquist
+/// frequencies is removed.
+#[derive(Clone, Debug)]
+pub struct Resampler<T: Float = f64> {
+    from: f64,
+    to: f64,
+    /// The number of rows of the filter table, excluding the repeated first row.
+    phases: usize,
+    /// The number of coefficients in each row of the filter table.
+    taps: usize,
+    /// The rows of the filter table, each ordered from the oldest input to the newest.
+    table: Vec<T>,
+    /// The delay of the filter in input samples.
+    delay: f64,
+    history: History<T>,
+    /// The number of positions between input samples.
+    denominator: u64,
+    /// The distance between output samples in positions.
+    step: u64,
+    /// The position of the next output sample after the newest input sample.
+    offset: u64,
+}
+
+impl<T: Float> Resampler<T> {
+    /// Creates a converter from one sample rate to another (hz).
+    ///
+    /// # Panics
+    ///
+    /// Panics if either sample rate isn't positive and finite.
+    pub fn new(from: f64, to: f64) -> Resampler<T> {
+        assert!(
+            from.is_finite() && from > 0.0,
+            "invalid sample rate {}",
+            from
+        );
+        assert!(to.is_finite() && to > 0.0, "invalid sample rate {}", to);
+        let (denominator, step) = if from.fract() == 0.0 && to.fract() == 0.0 {
+            let divisor = gcd(from as u64, to as u64);
+            (to as u64 / divisor, from as u64 / divisor)
+        } else {
+            let step = (FRACTIONAL_RESOLUTION as f64 * from / to).round() as u64;
+            (FRACTIONAL_RESOLUTION, step.max(1))
+        };
+
+        // design the filter at the rate of the upsampled input, with a transition band that
+        // ends at the lower Nyquist frequency
+        let phases = denominator.min(MAX_PHASES) as usize;
+        let taps = (TAPS as f64 * (from / to).max(1.0)).ceil() as usize;
+        let length = (taps * phases) as f64;
+        let transition = 4.0 / taps as f64 * from;
+        let cutoff = (from.min(to) - transition) / 2.0;
+        let filter = lowpass_filter(cutoff / (phases as f64 * from), 4.0 / length);
+
+        let mut table = Vec::with_capacity((phases + 1) * taps);
+        for phase in 0..=phases {
+            table.extend((0..taps).rev().map(|tap| {
+                let coefficient = filter.get(phase + tap * phases).copied().unwrap_or(0.0);
+                T::from_f64(coefficient * phases as f64)
+            }));
+        }
+
+        Resampler {
+            from,
+            to,
+            phases,
+            taps,
+            table,
+            delay: (filter.len() - 1) as f64 / (2.0 * phases as f64),
+            history: History::new(taps),
+            denominator,
+            step,
+            offset: denominator,
+        }
+    }
+
+    /// Returns the input sample rate (hz).
+    pub fn from(&self) -> f64 {
+        self.from
+    }
+
+    /// Returns the output sample rate (hz).
+    pub fn to(&self) -> f64 {
+        self.to
+    }
+
+    /// Returns the delay of the converter in output samples.
+    pub fn latency(&self) -> f64 {
+        self.delay * self.to / self.from
+    }
+
+    /// Returns the most output samples produced from `length` input samples, so that an output
+    /// buffer can be reserved ahead of time.
+    pub fn output_capacity(&self, length: usize) -> usize {
+        (length as u64 * self.denominator / self.step) as usize + 1
+    }
+
+    /// Converts a block of input, appending the output samples that become available.
+    ///
+    /// No allocation takes place if `output` has [`Resampler::output_capacity`] to spare.
+    pub fn process(&mut self, input: &[T], output: &mut Vec<T>) {
+        for &sample in input {
+            self.history.push(sample);
+            self.offset -= self.denominator;
+            while self.offset < self.denominator {
+                output.push(self.interpolate());
+                self.offset += self.step;
+            }
+        }
+    }
+
+    /// Computes the output sample at the current offset from the newest input.
+    fn interpolate(&self) -> T {
+        let position = (self.offset * self.phases as u64) as f64 / self.denominator as f64;
+        let phase = position as usize;
+        let weight = T::from_f64(position.fract());
+        let (lower, upper) = self.table[phase * self.taps..][..2 * self.taps].split_at(self.taps);
+        lower
+            .iter()
+            .zip(upper)
+            .zip(self.history.samples())
+            .fold(T::zero(), |sum, ((&lower, &upper), &sample)| {
+                sum + (lower + (upper - lower) * weight) * sample
+            })
+    }
+
+    /// Silences the converter.
+    pub fn reset(&mut self) {
+        self.history.reset();
+        self.offset = self.denominator;
+    }
+}
+
+/// Converts a whole buffer from one sample rate to another (hz), compensating for the delay of
+/// the filter so the output is aligned with the input.
+pub fn resample<T: Float>(input: &[T], from: f64, to: f64) -> Vec<T> {
+    let mut resampler = Resampler::new(from, to);
+    let length = (input.len() as f64 * to / from).ceil() as usize;
+    resampler.offset += (resampler.delay * resampler.denominator as f64).round() as u64;
+
+    let mut output = Vec::with_capacity(resampler.output_capacity(input.len()) + 1);
+    resampler.process(input, &mut output);
+    let silence = vec![T::zero(); resampler.taps];
+    while output.len() < length {
+        resampler.process(&silence, &mut output);
+    }
+    output.truncate(length);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn sine(frequency: f64, sample_rate: f64, length: usize) -> Vec<f64> {
+        (0..length)
+            .map(|i| (2.0 * PI * frequency * i as f64 / sample_rate).sin())
+            .collect()
+    }
+
+    #[test]
+    fn test_resample() {
+        for (from, to) in [(44100.0, 48000.0), (48000.0, 44100.0), (44100.0, 44100.5)] {
+            let output = resample(&sine(1000.0, from, 4410), from, to);
+            let expected = sine(1000.0, to, output.len());
+            assert_eq!(output.len(), (4410.0 * to / from).ceil() as usize);
+            for (output, expected) in output.iter().zip(&expected).skip(200).take(3000) {
+                assert!((output - expected).abs() < 2e-3);
+            }
+        }
+
+        // content above the output's Nyquist frequency is removed
+        let output = resample(&sine(30000.0, 96000.0, 9600), 96000.0, 44100.0);
+        assert!(output[200..4000].iter().all(|sample| sample.abs() < 1e-3));
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_streaming() {
+        let input = sine(440.0, 44100.0, 1000);
+        let mut whole: Resampler<f64> = Resampler::new(44100.0, 96000.0);
+        let mut expected = Vec::new();
+        whole.process(&input, &mut expected);
+        assert!(expected.len() <= whole.output_capacity(input.len()));
+
+        let mut streamed = Resampler::new(44100.0, 96000.0);
+        let mut output = Vec::with_capacity(streamed.output_capacity(input.len()));
+        for chunk in input.chunks(37) {
+            streamed.process(chunk, &mut output);
+        }
+        assert_eq!(output, expected);
+        assert!((streamed.latency() - 32.0 * 96000.0 / 44100.0).abs() < 1.0);
+    }
+}
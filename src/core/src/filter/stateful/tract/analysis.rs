@@ -0,0 +1,290 @@
+//! Acoustic analysis of the vocal tract's current shape.
+//!
+//! The transfer function is evaluated directly from the wave-guide's scattering equations
+//! (a chain-matrix in the z-domain), so it describes exactly what the tract will sound like,
+//! including the nasal branch, attenuation and the labial, nasal and glottal reflections.
+//! The glottis is treated as closed (subglottal coupling is ignored).
+
+use super::Tract;
+use crate::float::Float;
+use std::f64::consts::PI;
+use std::ops::{Add, Div, Mul, Sub};
+
+/// The highest frequency searched for formants (hz).
+const FORMANT_MAX_FREQUENCY: f64 = 5500.0;
+/// The resolution of the formant search (hz).
+const FORMANT_RESOLUTION: f64 = 5.0;
+
+/// A resonance of the vocal tract.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Formant {
+    /// Centre frequency (hz).
+    pub frequency: f64,
+    /// Bandwidth between the -3dB points (hz).
+    pub bandwidth: f64,
+}
+
+impl<T: Float> Tract<T> {
+    /// Returns the magnitude of the tract's transfer function (lip and nose output over glottal
+    /// excitation) at each of the given frequencies (hz).
+    pub fn frequency_response(&self, frequencies: &[f64]) -> Vec<f64> {
+        frequencies
+            .iter()
+            .map(|&frequency| self.transfer(frequency).magnitude())
+            .collect()
+    }
+
+    /// Returns the formants of the tract's current shape, in order of frequency.
+    pub fn formants(&self) -> Vec<Formant> {
+        let max_frequency = FORMANT_MAX_FREQUENCY.min(self.sample_rate / 2.0);
+        let frequencies: Vec<f64> = (1..)
+            .map(|i| i as f64 * FORMANT_RESOLUTION)
+            .take_while(|&frequency| frequency < max_frequency)
+            .collect();
+        let magnitudes = self.frequency_response(&frequencies);
+
+        find_formants(&frequencies, &magnitudes)
+    }
+
+    /// Evaluates the transfer function at a given frequency (hz).
+    fn transfer(&self, frequency: f64) -> Complex {
+        // the wave-guide steps faster than the sample rate
+        let z = Complex::from_polar(1.0, 2.0 * PI * frequency / self.step_rate());
+        let a = self.attenuation.as_f64();
+        let oral = &self.oral;
+        let (k_left, k_right, k_nose) = (
+            self.velum.k_left.as_f64(),
+            self.velum.k_right.as_f64(),
+            self.velum.k_nose.as_f64(),
+        );
+
+        // Solve backwards from a unit output at the lips
+        let mut right = Complex::real(1.0);
+        let mut left = right * (a * self.k_labial.as_f64()) / z;
+        let mut nose = Complex::real(0.0);
+
+        for m in (1..self.oral_length).rev() {
+            if m == self.velum_index {
+                let (reflectance, gain) = self.nasal_reflectance(z);
+                let sum = (z * right / a - left * k_right) / (1.0 + k_right);
+                let nasal_left = reflectance * (1.0 + k_nose) * (left + sum) / (reflectance + 1.0);
+                let nasal_right = (left + sum) * (1.0 + k_nose) - nasal_left;
+                nose = nasal_right * gain;
+
+                let oral_right = sum - nasal_left;
+                left = (oral_right * k_left + (nasal_left + left) * (1.0 + k_left)) * a / z;
+                right = oral_right;
+            } else {
+                let k = oral.k[m].as_f64();
+                let oral_right = (z * right / a + left * k) / (1.0 - k);
+                left = (oral_right * k + left * (1.0 + k)) * a / z;
+                right = oral_right;
+            }
+        }
+
+        let excitation = z * right / a - left * self.k_glottal.as_f64();
+        (nose + 1.0) / excitation
+    }
+
+    /// Returns the reflectance of the nasal cavity (left-moving over entering components) at
+    /// its velopharyngeal end, and the gain from entering components to the nostrils.
+    fn nasal_reflectance(&self, z: Complex) -> (Complex, Complex) {
+        let a = self.attenuation.as_f64();
+        let nasal = &self.nasal;
+
+        let mut right = Complex::real(1.0);
+        let mut left = right * (a * self.k_nose.as_f64()) / z;
+        for m in (1..self.nasal_length).rev() {
+            let k = nasal.k[m].as_f64();
+            let nasal_right = (z * right / a + left * k) / (1.0 - k);
+            left = (nasal_right * k + left * (1.0 + k)) * a / z;
+            right = nasal_right;
+        }
+
+        let entering = z * right / a;
+        (left / entering, Complex::real(1.0) / entering)
+    }
+}
+
+/// Finds the formants (peaks) of a magnitude response sampled at ascending frequencies.
+pub fn find_formants(frequencies: &[f64], magnitudes: &[f64]) -> Vec<Formant> {
+    let decibels: Vec<f64> = magnitudes
+        .iter()
+        .map(|magnitude| 20.0 * magnitude.max(f64::MIN_POSITIVE).log10())
+        .collect();
+    let mut formants = Vec::new();
+
+    for i in 1..decibels.len().saturating_sub(1) {
+        let (previous, peak, next) = (decibels[i - 1], decibels[i], decibels[i + 1]);
+        if !(peak > previous && peak >= next) {
+            continue;
+        }
+
+        // Refine the peak with parabolic interpolation
+        let step = frequencies[i + 1] - frequencies[i];
+        let curvature = previous - 2.0 * peak + next;
+        let offset = if curvature == 0.0 {
+            0.0
+        } else {
+            0.5 * (previous - next) / curvature
+        };
+        let frequency = frequencies[i] + offset * step;
+        let level = peak - 0.25 * (previous - next) * offset - 3.0;
+
+        // Locate the -3dB points either side of the peak
+        let lower = (0..i).rev().find(|&j| decibels[j] < level).map(|j| {
+            let t = (level - decibels[j]) / (decibels[j + 1] - decibels[j]);
+            frequencies[j] + t * (frequencies[j + 1] - frequencies[j])
+        });
+        let upper = (i + 1..decibels.len())
+            .find(|&j| decibels[j] < level)
+            .map(|j| {
+                let t = (decibels[j - 1] - level) / (decibels[j - 1] - decibels[j]);
+                frequencies[j - 1] + t * (frequencies[j] - frequencies[j - 1])
+            });
+
+        let bandwidth = match (lower, upper) {
+            (Some(lower), Some(upper)) => upper - lower,
+            (Some(lower), None) => 2.0 * (frequency - lower),
+            (None, Some(upper)) => 2.0 * (upper - frequency),
+            (None, None) => continue,
+        };
+
+        formants.push(Formant {
+            frequency,
+            bandwidth,
+        });
+    }
+
+    formants
+}
+
+/// A minimal complex number for evaluating transfer functions.
+#[derive(Clone, Copy, Debug)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    fn real(re: f64) -> Complex {
+        Complex { re, im: 0.0 }
+    }
+
+    fn from_polar(magnitude: f64, phase: f64) -> Complex {
+        Complex {
+            re: magnitude * phase.cos(),
+            im: magnitude * phase.sin(),
+        }
+    }
+
+    fn magnitude(&self) -> f64 {
+        self.re.hypot(self.im)
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+    fn add(self, rhs: Complex) -> Complex {
+        Complex {
+            re: self.re + rhs.re,
+            im: self.im + rhs.im,
+        }
+    }
+}
+
+impl Add<f64> for Complex {
+    type Output = Complex;
+    fn add(self, rhs: f64) -> Complex {
+        self + Complex::real(rhs)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+    fn sub(self, rhs: Complex) -> Complex {
+        Complex {
+            re: self.re - rhs.re,
+            im: self.im - rhs.im,
+        }
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+    fn mul(self, rhs: Complex) -> Complex {
+        Complex {
+            re: self.re * rhs.re - self.im * rhs.im,
+            im: self.re * rhs.im + self.im * rhs.re,
+        }
+    }
+}
+
+impl Mul<f64> for Complex {
+    type Output = Complex;
+    fn mul(self, rhs: f64) -> Complex {
+        Complex {
+            re: self.re * rhs,
+            im: self.im * rhs,
+        }
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+    fn div(self, rhs: Complex) -> Complex {
+        let denominator = rhs.re * rhs.re + rhs.im * rhs.im;
+        Complex {
+            re: (self.re * rhs.re + self.im * rhs.im) / denominator,
+            im: (self.im * rhs.re - self.re * rhs.im) / denominator,
+        }
+    }
+}
+
+impl Div<f64> for Complex {
+    type Output = Complex;
+    fn div(self, rhs: f64) -> Complex {
+        self * (1.0 / rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::vowels::Vowel;
+    use super::*;
+
+    fn formants_of(vowel: Vowel) -> Vec<Formant> {
+        let mut tract = Tract::new(44, 28);
+        tract.set_vowel(vowel);
+        tract.oral.diameter = tract.oral_diameter_target.clone();
+        tract.init();
+        tract.formants()
+    }
+
+    #[test]
+    fn test_find_formants() {
+        let frequencies: Vec<f64> = (0..100).map(|i| i as f64 * 10.0).collect();
+        let magnitudes: Vec<f64> = frequencies
+            .iter()
+            .map(|f| 1.0 / (1.0 + ((f - 500.0) / 50.0).powi(2)).sqrt())
+            .collect();
+
+        let formants = find_formants(&frequencies, &magnitudes);
+        assert_eq!(formants.len(), 1);
+        assert!((formants[0].frequency - 500.0).abs() < 1.0);
+        assert!((formants[0].bandwidth - 100.0).abs() < 5.0);
+    }
+
+    #[test]
+    fn test_vowel_formants() {
+        let i = formants_of(Vowel::I);
+        let a = formants_of(Vowel::A);
+        let u = formants_of(Vowel::U);
+
+        // /a/ is open (high F1), /i/ is front (high F2) and /u/ is back and rounded (low F2)
+        assert!(a[0].frequency > i[0].frequency);
+        assert!(a[0].frequency > u[0].frequency);
+        assert!(i[1].frequency > a[1].frequency);
+        assert!(i[1].frequency > u[1].frequency);
+    }
+}
@@ -0,0 +1,190 @@
+//! Area functions (cross-sectional area against distance from the glottis).
+//!
+//! Area functions are read from CSV, one `distance,area` pair per line with distances in
+//! centimetres and areas in square centimetres. Blank lines, `#` comments and a header row are
+//! ignored, so measured data such as MRI-derived vowel shapes can be loaded as-is.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Error, ErrorKind, Result};
+use std::path::Path;
+
+/// Reference vowel shapes bundled with the library, keyed by IPA symbol.
+///
+/// These are coarse (13 point) approximations of published MRI-derived area functions for an
+/// adult male speaker, intended as starting points rather than measurements.
+const REFERENCE_VOWELS: [(&str, &str); 6] = [
+    ("i", include_str!("data/i.csv")),
+    ("e", include_str!("data/e.csv")),
+    ("a", include_str!("data/a.csv")),
+    ("o", include_str!("data/o.csv")),
+    ("u", include_str!("data/u.csv")),
+    ("ə", include_str!("data/schwa.csv")),
+];
+
+/// Cross-sectional area sampled at increasing distances from the glottis.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AreaFunction {
+    /// Distance of each sample from the glottis (cm).
+    pub distances: Vec<f64>,
+    /// Cross-sectional area at each sample (cm^2).
+    pub areas: Vec<f64>,
+}
+
+impl AreaFunction {
+    /// Creates a new area function, validating that distances increase and areas are non-negative.
+    pub fn new(distances: Vec<f64>, areas: Vec<f64>) -> Result<AreaFunction> {
+        if distances.len() != areas.len() || distances.len() < 2 {
+            return Err(invalid_data("an area function needs at least two samples"));
+        }
+        if distances.windows(2).any(|pair| pair[1] <= pair[0]) {
+            return Err(invalid_data("distances must be strictly increasing"));
+        }
+        if areas.iter().any(|&area| !(area >= 0.0 && area.is_finite())) {
+            return Err(invalid_data("areas must be finite and non-negative"));
+        }
+
+        Ok(AreaFunction { distances, areas })
+    }
+
+    /// Creates an area function from uniformly spaced sections of `section_length` cm,
+    /// sampling each section at its centre.
+    pub fn from_sections(areas: Vec<f64>, section_length: f64) -> Result<AreaFunction> {
+        let distances = (0..areas.len())
+            .map(|m| (m as f64 + 0.5) * section_length)
+            .collect();
+        AreaFunction::new(distances, areas)
+    }
+
+    /// Parses an area function from CSV.
+    pub fn from_csv<R: BufRead>(reader: R) -> Result<AreaFunction> {
+        let mut distances = Vec::new();
+        let mut areas = Vec::new();
+        let mut header_seen = false;
+
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut columns = line.split(',').map(|column| column.trim().parse::<f64>());
+            match (columns.next(), columns.next()) {
+                (Some(Ok(distance)), Some(Ok(area))) => {
+                    distances.push(distance);
+                    areas.push(area);
+                }
+                // allow a single header row before the first sample
+                _ if distances.is_empty() && !header_seen => header_seen = true,
+                _ => {
+                    return Err(invalid_data(&format!(
+                        "malformed area function sample on line {}",
+                        number + 1
+                    )))
+                }
+            }
+        }
+
+        AreaFunction::new(distances, areas)
+    }
+
+    /// Returns a bundled reference vowel shape for a given IPA symbol.
+    pub fn reference(symbol: &str) -> Option<AreaFunction> {
+        REFERENCE_VOWELS
+            .iter()
+            .find(|(key, _)| *key == symbol)
+            .map(|(_, csv)| AreaFunction::from_csv(csv.as_bytes()).unwrap())
+    }
+
+    /// Returns the IPA symbols of every bundled reference vowel shape.
+    pub fn reference_symbols() -> impl Iterator<Item = &'static str> {
+        REFERENCE_VOWELS.iter().map(|(key, _)| *key)
+    }
+
+    /// Returns the distance from the glottis to the lips (cm).
+    pub fn length(&self) -> f64 {
+        self.distances[self.distances.len() - 1]
+    }
+
+    /// Returns the area at a given distance from the glottis using linear interpolation.
+    pub fn area_at(&self, distance: f64) -> f64 {
+        let last = self.distances.len() - 1;
+        if distance <= self.distances[0] {
+            return self.areas[0];
+        } else if distance >= self.distances[last] {
+            return self.areas[last];
+        }
+
+        let i = self.distances.partition_point(|&d| d <= distance);
+        let (d0, d1) = (self.distances[i - 1], self.distances[i]);
+        let (a0, a1) = (self.areas[i - 1], self.areas[i]);
+        a0 + (a1 - a0) * (distance - d0) / (d1 - d0)
+    }
+
+    /// Resamples the area function onto `sections` uniform sections spanning its whole length.
+    pub fn resample(&self, sections: usize) -> Vec<f64> {
+        let start = self.distances[0];
+        let span = self.length() - start;
+        (0..sections)
+            .map(|m| self.area_at(start + span * (m as f64 + 0.5) / sections as f64))
+            .collect()
+    }
+
+    /// Resamples the area function onto `sections` uniform sections, returning their diameters.
+    pub fn diameters(&self, sections: usize) -> Vec<f64> {
+        self.resample(sections)
+            .iter()
+            .map(|area| 2.0 * (area / std::f64::consts::PI).sqrt())
+            .collect()
+    }
+}
+
+/// Convenience method for parsing an `AreaFunction` from a CSV filepath.
+pub fn read_area_function_file<P: AsRef<Path>>(path: P) -> Result<AreaFunction> {
+    let file = File::open(path)?;
+    AreaFunction::from_csv(BufReader::new(file))
+}
+
+fn invalid_data(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_csv() {
+        let csv = "# comment\ndistance,area\n0.0, 1.0\n\n2.0, 3.0\n";
+        let area_function = AreaFunction::from_csv(csv.as_bytes()).unwrap();
+        assert_eq!(area_function.distances, vec![0.0, 2.0]);
+        assert_eq!(area_function.areas, vec![1.0, 3.0]);
+
+        // the header may follow any number of comments
+        let csv = "# a\n# b\n# c\ndistance,area\n0.0, 1.0\n2.0, 3.0\n";
+        assert!(AreaFunction::from_csv(csv.as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn test_reject_malformed_csv() {
+        assert!(AreaFunction::from_csv("0.0,1.0\n1.0,oops\n".as_bytes()).is_err());
+        assert!(AreaFunction::from_csv("0.0,1.0\n1.0,-1.0\n".as_bytes()).is_err());
+        assert!(AreaFunction::from_csv("1.0,1.0\n0.0,1.0\n".as_bytes()).is_err());
+        assert!(AreaFunction::from_csv("distance,area\nd,a\n0.0,1.0\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_resample() {
+        let area_function = AreaFunction::new(vec![0.0, 4.0], vec![0.0, 4.0]).unwrap();
+        assert_eq!(area_function.resample(4), vec![0.5, 1.5, 2.5, 3.5]);
+    }
+
+    #[test]
+    fn test_reference_vowels() {
+        for symbol in AreaFunction::reference_symbols() {
+            let area_function = AreaFunction::reference(symbol).unwrap();
+            assert_eq!(area_function.length(), 17.0);
+        }
+        assert!(AreaFunction::reference("x").is_none());
+    }
+}
@@ -0,0 +1,353 @@
+//! Validated construction of a [`Tract`].
+
+use super::cavity::Cavity;
+use super::error::TractError;
+use super::subglottis::Subglottis;
+use super::utils::min;
+use super::velum::Velum;
+use super::vowels::GRID_LENGTH;
+use super::{neutral_diameters, Tract, ATTENUATION, K_GLOTTAL, K_LABIAL, K_NOSE, VELUM_INDEX};
+use crate::filter::stateful::decimator::{Decimator, Oversampling};
+use crate::float::Float;
+
+/// The fewest sections an oral cavity may have (glottal, pharyngeal and buccal sections).
+pub const MIN_ORAL_LENGTH: usize = 6;
+/// The fewest sections a nasal or subglottal cavity may have.
+pub const MIN_CAVITY_LENGTH: usize = 2;
+
+/// Configures and validates a [`Tract`].
+///
+/// Lengths are given in sections at [`Oversampling::X2`], and are scaled with the oversampling
+/// factor when the tract is built.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TractBuilder {
+    oral_length: usize,
+    nasal_length: usize,
+    /// The velum's section, or `None` to place it in proportion to the oral length.
+    velum_index: Option<usize>,
+    glottal_reflection: f64,
+    labial_reflection: f64,
+    nasal_reflection: f64,
+    attenuation: f64,
+    sample_rate: f64,
+    subglottis: Option<usize>,
+    oversampling: Oversampling,
+}
+
+impl Default for TractBuilder {
+    fn default() -> TractBuilder {
+        TractBuilder {
+            oral_length: 44,
+            nasal_length: 28,
+            velum_index: None,
+            glottal_reflection: K_GLOTTAL,
+            labial_reflection: K_LABIAL,
+            nasal_reflection: K_NOSE,
+            attenuation: ATTENUATION,
+            sample_rate: 44100.0,
+            subglottis: None,
+            oversampling: Oversampling::X2,
+        }
+    }
+}
+
+impl TractBuilder {
+    /// Creates a builder with the default configuration (a 44 section oral cavity).
+    pub fn new() -> TractBuilder {
+        TractBuilder::default()
+    }
+
+    /// Sets the number of sections in the oral cavity.
+    pub fn oral_length(mut self, oral_length: usize) -> TractBuilder {
+        self.oral_length = oral_length;
+        self
+    }
+
+    /// Sets the number of sections in the nasal cavity.
+    pub fn nasal_length(mut self, nasal_length: usize) -> TractBuilder {
+        self.nasal_length = nasal_length;
+        self
+    }
+
+    /// Sets the oral section at which the nasal cavity branches.
+    ///
+    /// By default the velum is placed in proportion to the oral length (section 17 of 44).
+    pub fn velum_index(mut self, velum_index: usize) -> TractBuilder {
+        self.velum_index = Some(velum_index);
+        self
+    }
+
+    /// Scales a length at [`Oversampling::X2`] to the oversampling factor, halving (and
+    /// rounding up) at [`Oversampling::X1`].
+    fn scale(&self, length: usize) -> usize {
+        (length * self.oversampling.factor()).div_ceil(2)
+    }
+
+    /// Returns the oral section at which the nasal cavity branches, scaled to the oversampling
+    /// factor.
+    fn resolved_velum_index(&self) -> usize {
+        match self.velum_index {
+            Some(velum_index) => self.scale(velum_index),
+            None => {
+                let oral_length = self.scale(self.oral_length) as f64;
+                (VELUM_INDEX as f64 * oral_length / GRID_LENGTH).round() as usize
+            }
+        }
+    }
+
+    /// Sets the coefficient of reflection at the glottis.
+    pub fn glottal_reflection(mut self, k: f64) -> TractBuilder {
+        self.glottal_reflection = k;
+        self
+    }
+
+    /// Sets the coefficient of reflection at the lips.
+    pub fn labial_reflection(mut self, k: f64) -> TractBuilder {
+        self.labial_reflection = k;
+        self
+    }
+
+    /// Sets the coefficient of reflection at the nostrils.
+    pub fn nasal_reflection(mut self, k: f64) -> TractBuilder {
+        self.nasal_reflection = k;
+        self
+    }
+
+    /// Sets the attenuation applied as sound travels through each section.
+    pub fn attenuation(mut self, attenuation: f64) -> TractBuilder {
+        self.attenuation = attenuation;
+        self
+    }
+
+    /// Sets the sample rate at which the tract is processed.
+    pub fn sample_rate(mut self, sample_rate: f64) -> TractBuilder {
+        self.sample_rate = sample_rate;
+        self
+    }
+
+    /// Sets the number of wave-guide steps per sample, whose output is decimated to the
+    /// sample rate.
+    ///
+    /// Sound crosses a section in each step, so the tract is built with its lengths scaled by
+    /// the number of steps to keep its formants: at [`Oversampling::X4`] the default tract has
+    /// 88 oral sections and sounds as at [`Oversampling::X2`] with less aliasing. Oversampling
+    /// is fixed once the tract is built.
+    pub fn oversampling(mut self, oversampling: Oversampling) -> TractBuilder {
+        self.oversampling = oversampling;
+        self
+    }
+
+    /// Couples a subglottal tract of `length` sections (see [`Tract::enable_subglottis`]).
+    pub fn subglottis(mut self, length: Option<usize>) -> TractBuilder {
+        self.subglottis = length;
+        self
+    }
+
+    /// Checks the configuration without building a tract.
+    ///
+    /// Lengths are checked once scaled to the oversampling factor.
+    pub fn validate(&self) -> Result<(), TractError> {
+        let oral_length = self.scale(self.oral_length);
+        if oral_length < MIN_ORAL_LENGTH {
+            return Err(TractError::InvalidLength {
+                cavity: "oral",
+                length: oral_length,
+            });
+        }
+        let nasal_length = self.scale(self.nasal_length);
+        if nasal_length < MIN_CAVITY_LENGTH {
+            return Err(TractError::InvalidLength {
+                cavity: "nasal",
+                length: nasal_length,
+            });
+        }
+        if let Some(length) = self
+            .subglottis
+            .map(|length| self.scale(length))
+            .filter(|&length| length < MIN_CAVITY_LENGTH)
+        {
+            return Err(TractError::InvalidLength {
+                cavity: "subglottal",
+                length,
+            });
+        }
+        // The velum scatters between the sections either side of it
+        let velum_index = self.resolved_velum_index();
+        if velum_index == 0 || velum_index + 1 >= oral_length {
+            return Err(TractError::InvalidVelumIndex {
+                index: velum_index,
+                oral_length,
+            });
+        }
+        for &(name, k) in &[
+            ("glottal", self.glottal_reflection),
+            ("labial", self.labial_reflection),
+            ("nasal", self.nasal_reflection),
+        ] {
+            if !(k.is_finite() && k.abs() <= 1.0) {
+                return Err(TractError::InvalidReflection { name, value: k });
+            }
+        }
+        if !(self.attenuation > 0.0 && self.attenuation <= 1.0) {
+            return Err(TractError::InvalidAttenuation(self.attenuation));
+        }
+        if !(self.sample_rate.is_finite() && self.sample_rate > 0.0) {
+            return Err(TractError::InvalidSampleRate(self.sample_rate));
+        }
+        Ok(())
+    }
+
+    /// Validates the configuration and builds a tract of precision `T`.
+    pub fn build<T: Float>(&self) -> Result<Tract<T>, TractError> {
+        self.validate()?;
+        let (oral_length, nasal_length) =
+            (self.scale(self.oral_length), self.scale(self.nasal_length));
+        let mut oral = Cavity::new(oral_length);
+        let mut nasal = Cavity::new(nasal_length);
+        let velum = Velum::new(0.04, 0.1);
+
+        // Initalialise oral cavity
+        let oral_diameter_rest = neutral_diameters(oral_length);
+        let oral_diameter_target = oral_diameter_rest.clone();
+        oral.diameter.copy_from_slice(&oral_diameter_rest);
+
+        // Initalialise nasal cavity
+        // TODO try and get rid of magic numbers if possible
+        for i in 0..nasal_length {
+            let d = 2.0 * (i as f64 / nasal_length as f64);
+            let diameter = if d < 1.0 {
+                0.4 + (1.6 * d)
+            } else {
+                0.2 + 1.2 * (2.0 - d)
+            };
+            nasal.diameter[i] = min(diameter, 1.2) * nasal_length as f64 / 28.0
+        }
+
+        // Construct
+        Ok(Tract {
+            oral_length,
+            nasal_length,
+            oral,
+            nasal,
+            velum,
+            velum_index: self.resolved_velum_index(),
+            k_glottal: T::from_f64(self.glottal_reflection),
+            k_labial: T::from_f64(self.labial_reflection),
+            k_nose: T::from_f64(self.nasal_reflection),
+            attenuation: T::from_f64(self.attenuation),
+            oral_diameter_rest,
+            oral_diameter_target,
+            transients: Vec::new(),
+            last_obstruction: None,
+            constriction: None,
+            sample_rate: self.sample_rate,
+            subglottis: self
+                .subglottis
+                .map(|length| Subglottis::new(self.scale(length))),
+            glottal_opening: 0.0,
+            error: None,
+            recoveries: 0,
+            decimator: Decimator::new(self.oversampling),
+        })
+    }
+}
+
+impl<T: Float> Tract<T> {
+    /// Returns a builder with this tract's configuration, its lengths given at
+    /// [`Oversampling::X2`] (so odd lengths built at [`Oversampling::X1`] are rounded up).
+    pub fn builder(&self) -> TractBuilder {
+        let factor = self.oversampling().factor();
+        let unscale = |length: usize| length * 2 / factor;
+        TractBuilder {
+            oral_length: unscale(self.oral_length),
+            nasal_length: unscale(self.nasal_length),
+            velum_index: Some(unscale(self.velum_index)),
+            glottal_reflection: self.k_glottal.as_f64(),
+            labial_reflection: self.k_labial.as_f64(),
+            nasal_reflection: self.k_nose.as_f64(),
+            attenuation: self.attenuation.as_f64(),
+            sample_rate: self.sample_rate,
+            subglottis: self
+                .subglottis
+                .as_ref()
+                .map(|subglottis| unscale(subglottis.length)),
+            oversampling: self.oversampling(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build() {
+        let mut tract = TractBuilder::new()
+            .oral_length(30)
+            .nasal_length(20)
+            .velum_index(12)
+            .subglottis(Some(20))
+            .build::<f32>()
+            .unwrap();
+        tract.init();
+        tract.post_block();
+        for _ in 0..100 {
+            assert!(tract.process(0.1, 0.0).is_finite());
+        }
+    }
+
+    #[test]
+    fn test_reject_invalid_configuration() {
+        let invalid = [
+            TractBuilder::new().oral_length(2),
+            TractBuilder::new().nasal_length(0),
+            TractBuilder::new().oral_length(10).velum_index(17),
+            TractBuilder::new().velum_index(0),
+            TractBuilder::new().labial_reflection(-1.5),
+            TractBuilder::new().glottal_reflection(f64::NAN),
+            TractBuilder::new().attenuation(0.0),
+            TractBuilder::new().sample_rate(-44100.0),
+            TractBuilder::new().subglottis(Some(1)),
+        ];
+        for builder in invalid.iter() {
+            assert!(builder.build::<f64>().is_err(), "{:?}", builder);
+        }
+    }
+
+    #[test]
+    fn test_default_velum_index_scales() {
+        for (oral_length, velum_index) in [(44, 17), (88, 34), (10, 4), (MIN_ORAL_LENGTH, 2)] {
+            let tract = TractBuilder::new()
+                .oral_length(oral_length)
+                .build::<f64>()
+                .unwrap();
+            assert_eq!(tract.velum_index, velum_index);
+        }
+    }
+
+    #[test]
+    fn test_builder_round_trip() {
+        let builder = TractBuilder::new().oral_length(40).labial_reflection(-0.8);
+        // the default velum index is resolved for the tract's length
+        let expected = builder.clone().velum_index(15);
+        assert_eq!(builder.build::<f64>().unwrap().builder(), expected);
+        assert_eq!(expected.build::<f64>().unwrap().builder(), expected);
+
+        // lengths are scaled to the oversampling factor and back
+        for oversampling in [Oversampling::X1, Oversampling::X4] {
+            let builder = expected
+                .clone()
+                .velum_index(16)
+                .subglottis(Some(20))
+                .oversampling(oversampling);
+            assert_eq!(builder.build::<f64>().unwrap().builder(), builder);
+        }
+        let tract = TractBuilder::new()
+            .oversampling(Oversampling::X4)
+            .build::<f64>()
+            .unwrap();
+        assert_eq!((tract.oral_length, tract.nasal_length), (88, 56));
+        assert_eq!(tract.velum_index, 2 * VELUM_INDEX);
+    }
+}
@@ -1,32 +1,121 @@
+use super::utils::{circle_area, kelly_lochbaum};
+use crate::float::Float;
+
 /// A line of sections through which sound can propagate.
+///
+/// The cavity's shape is described in `f64`, whereas its reflections and travelling
+/// components are of precision `T`.
 #[derive(Clone, Debug)]
-pub struct Cavity {
+pub struct Cavity<T: Float> {
     /// Diameter of each section.
     pub diameter: Vec<f64>,
     /// Cross-sectional area of each section.
     pub area: Vec<f64>,
     /// Reflections at each section.
-    pub k: Vec<f64>,
+    pub k: Vec<T>,
     /// Left-moving components at section.
-    pub left: Vec<f64>,
+    pub left: Vec<T>,
     /// Right-moving components at section.
-    pub right: Vec<f64>,
+    pub right: Vec<T>,
     /// Left-moving components at junction.
-    pub j_left: Vec<f64>,
+    pub j_left: Vec<T>,
     /// Right-moving components at junction.
-    pub j_right: Vec<f64>,
+    pub j_right: Vec<T>,
+    /// Change in reflection at each junction per interpolation step.
+    pub dk: Vec<T>,
+    /// Reflection at each junction from which the next interpolation starts.
+    pub held_k: Vec<T>,
 }
 
-impl Cavity {
-    pub fn new(size: usize) -> Cavity {
+impl<T: Float> Cavity<T> {
+    pub fn new(size: usize) -> Cavity<T> {
         Cavity {
             diameter: vec![0.0; size],
             area: vec![0.0; size],
-            left: vec![0.0; size],
-            right: vec![0.0; size],
-            k: vec![0.0; size + 1],
-            j_left: vec![0.0; size + 1],
-            j_right: vec![0.0; size + 1],
+            left: vec![T::zero(); size],
+            right: vec![T::zero(); size],
+            k: vec![T::zero(); size + 1],
+            j_left: vec![T::zero(); size + 1],
+            j_right: vec![T::zero(); size + 1],
+            dk: vec![T::zero(); size + 1],
+            held_k: vec![T::zero(); size + 1],
+        }
+    }
+
+    /// Calculates the area of each section and the coefficient of reflection at each junction.
+    pub fn calculate_reflections(&mut self) {
+        for m in 0..self.diameter.len() {
+            self.area[m] = circle_area(self.diameter[m])
+        }
+        for m in 1..self.diameter.len() {
+            self.k[m] = T::from_f64(kelly_lochbaum(self.area[m - 1], self.area[m]))
+        }
+    }
+
+    /// Holds the current reflections as the start of the next interpolation.
+    pub fn hold_reflections(&mut self) {
+        self.held_k.copy_from_slice(&self.k);
+    }
+
+    /// Glides each reflection from its held value to its current value over `steps` calls to
+    /// [`Cavity::interpolate`], starting from the held value.
+    pub fn begin_interpolation(&mut self, steps: usize) {
+        let scale = T::one() / T::from_f64(steps.max(1) as f64);
+        for ((k, dk), &held) in self.k.iter_mut().zip(self.dk.iter_mut()).zip(&self.held_k) {
+            *dk = (*k - held) * scale;
+            *k = held;
+        }
+    }
+
+    /// Advances each reflection by one interpolation step.
+    pub fn interpolate(&mut self) {
+        for (k, &dk) in self.k.iter_mut().zip(&self.dk) {
+            *k += dk;
+        }
+    }
+
+    /// Silences every travelling component without changing the cavity's shape.
+    pub fn reset(&mut self) {
+        for values in [
+            &mut self.left,
+            &mut self.right,
+            &mut self.j_left,
+            &mut self.j_right,
+        ] {
+            values.iter_mut().for_each(|value| *value = T::zero());
+        }
+    }
+
+    /// Calculates the reflection (w) at each inner junction.
+    ///
+    /// The cavity is stored as a structure of arrays and each junction is updated independently
+    /// from zipped slices (free of bounds checks), allowing the loop to auto-vectorise.
+    pub fn scatter(&mut self) {
+        let n = self.diameter.len();
+        let junctions = self.j_right[1..n]
+            .iter_mut()
+            .zip(self.j_left[1..n].iter_mut())
+            .zip(
+                self.k[1..n]
+                    .iter()
+                    .zip(&self.right[..n - 1])
+                    .zip(&self.left[1..n]),
+            );
+        for ((j_right, j_left), ((&k, &right), &left)) in junctions {
+            let w = k * (right + left);
+            *j_right = right - w;
+            *j_left = left + w;
+        }
+    }
+
+    /// Transfers the attenuated junction components into each section.
+    pub fn propagate(&mut self, attenuation: T) {
+        let n = self.diameter.len();
+        for (right, &j_right) in self.right.iter_mut().zip(&self.j_right[..n]) {
+            *right = j_right * attenuation;
+        }
+        for (left, &j_left) in self.left.iter_mut().zip(&self.j_left[1..]) {
+            *left = j_left * attenuation;
         }
     }
 }
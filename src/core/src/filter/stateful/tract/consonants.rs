@@ -0,0 +1,397 @@
+//! Consonants as articulatory gestures on the vocal tract, keyed by IPA symbol.
+//!
+//! A [`Gesture`] executes a [`Consonant`] over time, moving a [`Constriction`] from open to its
+//! place of articulation (closure), holding it, then releasing it. Each tick yields a
+//! [`GestureFrame`] describing the constriction, velum, voicing and aspiration to apply to the
+//! voice.
+
+use std::f64::consts::PI;
+
+/// The diameter of an unobstructed constriction (no narrower than any resting section).
+const OPEN_DIAMETER: f64 = 3.0;
+/// The diameter of a fricative constriction, narrow enough to generate turbulence.
+const FRICATIVE_DIAMETER: f64 = 0.8;
+/// The diameter of an approximant constriction.
+const APPROXIMANT_DIAMETER: f64 = 1.2;
+/// The rate at which the tongue tip vibrates during a trill (hz).
+const TRILL_FREQUENCY: f64 = 25.0;
+/// The voicing maintained by voiced obstruents during closure.
+const OBSTRUENT_VOICING: f64 = 0.7;
+
+/// A narrowing of the oral cavity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Constriction {
+    /// Position of the constriction along the tract in range [0-1] (glottis to lips).
+    pub index: f64,
+    /// Diameter of the constriction (0 being a complete closure).
+    pub diameter: f64,
+    /// The intensity of turbulence noise generated at the constriction in range [0-1].
+    pub fricative_intensity: f64,
+}
+
+/// The place of articulation of a consonant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Place {
+    Bilabial,
+    Labiodental,
+    Dental,
+    Alveolar,
+    Postalveolar,
+    Palatal,
+    Velar,
+    Glottal,
+}
+
+impl Place {
+    /// Returns the position of the place of articulation along the tract in range [0-1].
+    ///
+    /// Glottal consonants are articulated by the glottis and do not constrict the tract, so they
+    /// are heard through voicing and aspiration alone.
+    pub fn index(&self) -> Option<f64> {
+        let index = match self {
+            Place::Bilabial => 41.5,
+            Place::Labiodental => 40.0,
+            Place::Dental => 38.0,
+            Place::Alveolar => 36.0,
+            Place::Postalveolar => 33.5,
+            Place::Palatal => 29.0,
+            Place::Velar => 24.0,
+            Place::Glottal => return None,
+        };
+        Some(index / 44.0)
+    }
+}
+
+/// The manner of articulation of a consonant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Manner {
+    /// Complete closure followed by a release burst.
+    Stop,
+    /// Narrow constriction generating turbulence.
+    Fricative,
+    /// A stop released into a fricative.
+    Affricate,
+    /// Complete oral closure with the velum lowered.
+    Nasal,
+    /// Narrowing without turbulence.
+    Approximant,
+    /// Repeated closures of the tongue tip.
+    Trill,
+}
+
+impl Manner {
+    /// Returns the default timing of a gesture of this manner.
+    pub fn timing(&self) -> Timing {
+        match self {
+            Manner::Stop => Timing::new(0.04, 0.06, 0.04),
+            Manner::Fricative => Timing::new(0.04, 0.10, 0.04),
+            Manner::Affricate => Timing::new(0.04, 0.05, 0.08),
+            Manner::Nasal => Timing::new(0.04, 0.07, 0.04),
+            Manner::Approximant => Timing::new(0.05, 0.04, 0.06),
+            Manner::Trill => Timing::new(0.03, 0.08, 0.03),
+        }
+    }
+
+    /// Returns the diameter of the constriction held by this manner.
+    fn diameter(&self) -> f64 {
+        match self {
+            Manner::Stop | Manner::Affricate | Manner::Nasal => 0.0,
+            Manner::Fricative => FRICATIVE_DIAMETER,
+            Manner::Approximant => APPROXIMANT_DIAMETER,
+            Manner::Trill => FRICATIVE_DIAMETER / 2.0,
+        }
+    }
+}
+
+/// A consonant described by its place and manner of articulation and its voicing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Consonant {
+    pub place: Place,
+    pub manner: Manner,
+    pub voiced: bool,
+}
+
+/// Every consonant in the library with its IPA symbol.
+const CONSONANTS: [(&str, Place, Manner, bool); 30] = [
+    ("p", Place::Bilabial, Manner::Stop, false),
+    ("b", Place::Bilabial, Manner::Stop, true),
+    ("t", Place::Alveolar, Manner::Stop, false),
+    ("d", Place::Alveolar, Manner::Stop, true),
+    ("k", Place::Velar, Manner::Stop, false),
+    ("g", Place::Velar, Manner::Stop, true),
+    ("ʔ", Place::Glottal, Manner::Stop, false),
+    ("m", Place::Bilabial, Manner::Nasal, true),
+    ("n", Place::Alveolar, Manner::Nasal, true),
+    ("ɲ", Place::Palatal, Manner::Nasal, true),
+    ("ŋ", Place::Velar, Manner::Nasal, true),
+    ("f", Place::Labiodental, Manner::Fricative, false),
+    ("v", Place::Labiodental, Manner::Fricative, true),
+    ("θ", Place::Dental, Manner::Fricative, false),
+    ("ð", Place::Dental, Manner::Fricative, true),
+    ("s", Place::Alveolar, Manner::Fricative, false),
+    ("z", Place::Alveolar, Manner::Fricative, true),
+    ("ʃ", Place::Postalveolar, Manner::Fricative, false),
+    ("ʒ", Place::Postalveolar, Manner::Fricative, true),
+    ("ç", Place::Palatal, Manner::Fricative, false),
+    ("x", Place::Velar, Manner::Fricative, false),
+    ("h", Place::Glottal, Manner::Fricative, false),
+    ("tʃ", Place::Postalveolar, Manner::Affricate, false),
+    ("dʒ", Place::Postalveolar, Manner::Affricate, true),
+    ("w", Place::Bilabial, Manner::Approximant, true),
+    ("l", Place::Alveolar, Manner::Approximant, true),
+    ("ɹ", Place::Postalveolar, Manner::Approximant, true),
+    ("j", Place::Palatal, Manner::Approximant, true),
+    ("ɰ", Place::Velar, Manner::Approximant, true),
+    ("r", Place::Alveolar, Manner::Trill, true),
+];
+
+impl Consonant {
+    pub fn new(place: Place, manner: Manner, voiced: bool) -> Consonant {
+        Consonant {
+            place,
+            manner,
+            voiced,
+        }
+    }
+
+    /// Returns every consonant in the library.
+    pub fn all() -> impl Iterator<Item = Consonant> {
+        CONSONANTS
+            .iter()
+            .map(|&(_, place, manner, voiced)| Consonant::new(place, manner, voiced))
+    }
+
+    /// Returns the consonant for a given IPA symbol.
+    pub fn from_ipa(symbol: &str) -> Option<Consonant> {
+        CONSONANTS
+            .iter()
+            .find(|entry| entry.0 == symbol)
+            .map(|&(_, place, manner, voiced)| Consonant::new(place, manner, voiced))
+    }
+
+    /// Returns the consonant's IPA symbol, if it is in the library.
+    pub fn ipa(&self) -> Option<&'static str> {
+        CONSONANTS
+            .iter()
+            .find(|&&(_, place, manner, voiced)| Consonant::new(place, manner, voiced) == *self)
+            .map(|entry| entry.0)
+    }
+
+    /// Returns a gesture articulating this consonant with its default timing.
+    pub fn gesture(&self) -> Gesture {
+        Gesture::new(*self, self.manner.timing())
+    }
+}
+
+/// The duration of each phase of a gesture (seconds).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Timing {
+    /// Time taken to form the constriction.
+    pub closure: f64,
+    /// Time the constriction is held.
+    pub hold: f64,
+    /// Time taken to release the constriction.
+    pub release: f64,
+}
+
+impl Timing {
+    pub fn new(closure: f64, hold: f64, release: f64) -> Timing {
+        Timing {
+            closure,
+            hold,
+            release,
+        }
+    }
+
+    /// Returns the total duration of the gesture.
+    pub fn duration(&self) -> f64 {
+        self.closure + self.hold + self.release
+    }
+}
+
+/// The state of the articulators at an instant of a gesture.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GestureFrame {
+    /// The constriction of the oral cavity, if any.
+    pub constriction: Option<Constriction>,
+    /// Opening of the velum in range [0-1].
+    pub velum: f64,
+    /// Voicing of the glottal source in range [0-1].
+    pub voicing: f64,
+    /// Turbulence noise generated at the glottis in range [0-1].
+    pub aspiration: f64,
+}
+
+impl Default for GestureFrame {
+    fn default() -> GestureFrame {
+        GestureFrame {
+            constriction: None,
+            velum: 0.0,
+            voicing: 1.0,
+            aspiration: 0.0,
+        }
+    }
+}
+
+/// The execution of a consonant over time.
+#[derive(Clone, Debug)]
+pub struct Gesture {
+    pub consonant: Consonant,
+    pub timing: Timing,
+    elapsed: f64,
+}
+
+impl Gesture {
+    pub fn new(consonant: Consonant, timing: Timing) -> Gesture {
+        Gesture {
+            consonant,
+            timing,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Returns true once the constriction has been fully released.
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.timing.duration()
+    }
+
+    /// Returns the state of the articulators at the current time, then advances by `delta_time`.
+    pub fn tick(&mut self, delta_time: f64) -> GestureFrame {
+        let frame = self.frame_at(self.elapsed);
+        self.elapsed += delta_time;
+        frame
+    }
+
+    /// Returns the state of the articulators `time` seconds into the gesture.
+    pub fn frame_at(&self, time: f64) -> GestureFrame {
+        let Consonant {
+            place,
+            manner,
+            voiced,
+        } = self.consonant;
+        let Timing {
+            closure,
+            hold,
+            release,
+        } = self.timing;
+
+        if time < 0.0 || time >= self.timing.duration() {
+            return GestureFrame::default();
+        }
+
+        // progress through the gesture: 0 (open) -> 1 (held) -> 0 (released)
+        let (degree, releasing) = if time < closure {
+            (smooth(time / closure), false)
+        } else if time < closure + hold {
+            (1.0, false)
+        } else {
+            (1.0 - smooth((time - closure - hold) / release), true)
+        };
+
+        let target = match manner {
+            Manner::Trill if time >= closure && time < closure + hold => {
+                let phase = 2.0 * PI * TRILL_FREQUENCY * (time - closure);
+                manner.diameter() * (1.0 - phase.cos())
+            }
+            // affricates release slowly through a fricative constriction
+            Manner::Affricate if releasing => FRICATIVE_DIAMETER * (1.0 - degree),
+            _ => manner.diameter(),
+        };
+
+        let fricative_intensity = match manner {
+            Manner::Fricative | Manner::Affricate => 1.0,
+            Manner::Stop if releasing => 1.0,
+            _ => 0.0,
+        };
+
+        let constriction = place.index().map(|index| Constriction {
+            index,
+            diameter: OPEN_DIAMETER + (target - OPEN_DIAMETER) * degree.min(1.0),
+            fricative_intensity: fricative_intensity * degree,
+        });
+
+        let velum = if manner == Manner::Nasal { 1.0 } else { 0.0 };
+
+        let voicing = match (manner, voiced) {
+            (Manner::Nasal | Manner::Approximant | Manner::Trill, _) => 1.0,
+            (_, true) => 1.0 - (1.0 - OBSTRUENT_VOICING) * degree,
+            // voiceless stops remain unvoiced throughout their release (aspiration)
+            (Manner::Stop, false) if releasing => 0.0,
+            (_, false) => 1.0 - degree,
+        };
+
+        // breath passes through the open glottis of /h/ and the release of voiceless stops
+        let aspiration = match (place, manner, voiced) {
+            (Place::Glottal, Manner::Fricative, _) => degree,
+            (Place::Glottal, _, _) | (_, _, true) => 0.0,
+            (_, Manner::Stop, false) if releasing => degree,
+            _ => 0.0,
+        };
+
+        GestureFrame {
+            constriction,
+            velum,
+            voicing,
+            aspiration,
+        }
+    }
+}
+
+/// Returns a raised-cosine ease in range [0-1].
+fn smooth(x: f64) -> f64 {
+    0.5 * (1.0 - (PI * x.clamp(0.0, 1.0)).cos())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ipa_round_trip() {
+        for consonant in Consonant::all() {
+            let symbol = consonant.ipa().unwrap();
+            assert_eq!(Consonant::from_ipa(symbol), Some(consonant));
+        }
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_stop_closes_and_releases() {
+        let mut gesture = Consonant::from_ipa("p").unwrap().gesture();
+        let timing = gesture.timing;
+
+        let held = gesture.frame_at(timing.closure + timing.hold / 2.0);
+        assert_eq!(held.constriction.unwrap().diameter, 0.0);
+        assert_eq!(held.voicing, 0.0);
+
+        while !gesture.is_finished() {
+            gesture.tick(0.001);
+        }
+        assert_eq!(gesture.tick(0.001), GestureFrame::default());
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_nasal_lowers_velum() {
+        let gesture = Consonant::from_ipa("m").unwrap().gesture();
+        let frame = gesture.frame_at(gesture.timing.closure);
+        assert_eq!(frame.velum, 1.0);
+        assert_eq!(frame.voicing, 1.0);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_glottal_fricative_aspirates() {
+        let gesture = Consonant::from_ipa("h").unwrap().gesture();
+        let frame = gesture.frame_at(gesture.timing.closure);
+        assert_eq!(frame.constriction, None);
+        assert_eq!(frame.voicing, 0.0);
+        assert_eq!(frame.aspiration, 1.0);
+
+        let glottal_stop = Consonant::from_ipa("ʔ").unwrap().gesture();
+        let frame = glottal_stop.frame_at(glottal_stop.timing.closure);
+        assert_eq!(frame.voicing, 0.0);
+        assert_eq!(frame.aspiration, 0.0);
+    }
+}
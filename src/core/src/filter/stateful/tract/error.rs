@@ -0,0 +1,113 @@
+//! Errors and diagnostics reported by the vocal tract.
+
+use std::error::Error;
+use std::fmt;
+
+/// A problem detected in the vocal tract's shape or output.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TractError {
+    /// A diameter was negative or not finite.
+    InvalidDiameter { index: usize, diameter: f64 },
+    /// A shape did not have one diameter per section.
+    LengthMismatch { expected: usize, actual: usize },
+    /// A cavity had too few sections.
+    InvalidLength { cavity: &'static str, length: usize },
+    /// The velum was not between two sections of the oral cavity.
+    InvalidVelumIndex { index: usize, oral_length: usize },
+    /// A coefficient of reflection was not finite or outside of range [-1, 1].
+    InvalidReflection { name: &'static str, value: f64 },
+    /// The attenuation was outside of range (0, 1].
+    InvalidAttenuation(f64),
+    /// The sample rate was not finite and positive.
+    InvalidSampleRate(f64),
+    /// A formant target had no formants.
+    EmptyTarget,
+    /// A target formant frequency was not finite, positive and above the previous formant.
+    InvalidFormant { index: usize, frequency: f64 },
+    /// A target formant bandwidth was not finite and positive.
+    InvalidBandwidth { index: usize, bandwidth: f64 },
+    /// The output was NaN or infinite.
+    NonFinite,
+    /// The output exceeded the overflow threshold.
+    Overflow { value: f64 },
+}
+
+impl fmt::Display for TractError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TractError::InvalidDiameter { index, diameter } => {
+                write!(f, "invalid diameter {} at section {}", diameter, index)
+            }
+            TractError::LengthMismatch { expected, actual } => {
+                write!(f, "expected {} diameters but found {}", expected, actual)
+            }
+            TractError::InvalidLength { cavity, length } => {
+                write!(f, "the {} cavity cannot have {} sections", cavity, length)
+            }
+            TractError::InvalidVelumIndex { index, oral_length } => write!(
+                f,
+                "velum index {} is outside an oral cavity of {} sections",
+                index, oral_length
+            ),
+            TractError::InvalidReflection { name, value } => {
+                write!(f, "invalid {} reflection {}", name, value)
+            }
+            TractError::InvalidAttenuation(value) => write!(f, "invalid attenuation {}", value),
+            TractError::InvalidSampleRate(value) => write!(f, "invalid sample rate {}", value),
+            TractError::EmptyTarget => write!(f, "the formant target has no formants"),
+            TractError::InvalidFormant { index, frequency } => {
+                write!(
+                    f,
+                    "invalid frequency {} for formant {}",
+                    frequency,
+                    index + 1
+                )
+            }
+            TractError::InvalidBandwidth { index, bandwidth } => {
+                write!(
+                    f,
+                    "invalid bandwidth {} for formant {}",
+                    bandwidth,
+                    index + 1
+                )
+            }
+            TractError::NonFinite => write!(f, "tract output was not finite"),
+            TractError::Overflow { value } => write!(f, "tract output overflowed ({})", value),
+        }
+    }
+}
+
+impl Error for TractError {}
+
+/// Checks that every diameter is finite and non-negative.
+pub fn validate_diameters(diameters: &[f64]) -> Result<(), TractError> {
+    match diameters
+        .iter()
+        .position(|diameter| !(diameter.is_finite() && *diameter >= 0.0))
+    {
+        Some(index) => Err(TractError::InvalidDiameter {
+            index,
+            diameter: diameters[index],
+        }),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_diameters() {
+        assert!(validate_diameters(&[0.0, 1.0, 3.5]).is_ok());
+        assert_eq!(
+            validate_diameters(&[1.0, -0.5]),
+            Err(TractError::InvalidDiameter {
+                index: 1,
+                diameter: -0.5
+            })
+        );
+        assert!(validate_diameters(&[f64::NAN]).is_err());
+        assert!(validate_diameters(&[f64::INFINITY]).is_err());
+    }
+}
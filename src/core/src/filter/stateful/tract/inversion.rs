@@ -0,0 +1,426 @@
+//! Formant-to-area inversion.
+//!
+//! Finds a smooth area function whose formants match a target by perturbing the tract's resting
+//! shape with a low-order cosine basis (in log-diameter) and minimising the formant error with a
+//! damped Gauss-Newton (Levenberg-Marquardt) method using finite-difference sensitivities.
+
+use super::analysis::{find_formants, Formant};
+use super::error::TractError;
+use super::Tract;
+use crate::float::Float;
+use std::f64::consts::PI;
+
+/// The narrowest diameter permitted when fitting (anatomically plausible, never a closure).
+const MIN_DIAMETER: f64 = 0.3;
+/// The widest diameter permitted when fitting.
+const MAX_DIAMETER: f64 = 3.5;
+/// The resolution of the formant search while fitting (hz).
+const SEARCH_RESOLUTION: f64 = 10.0;
+/// The highest frequency searched for formants while fitting (hz).
+const SEARCH_MAX_FREQUENCY: f64 = 5500.0;
+/// The step used to estimate formant sensitivity to each coefficient.
+const SENSITIVITY_STEP: f64 = 1e-2;
+/// The relative weight of bandwidth errors compared to frequency errors.
+const BANDWIDTH_WEIGHT: f64 = 0.25;
+/// The residual applied to each formant that could not be found.
+const MISSING_FORMANT_PENALTY: f64 = 12.0;
+
+/// Target formants for inversion.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormantTarget {
+    /// Target frequencies in ascending order, starting with F1 (hz).
+    pub frequencies: Vec<f64>,
+    /// Optional target bandwidths for each frequency (hz).
+    pub bandwidths: Option<Vec<f64>>,
+}
+
+impl FormantTarget {
+    pub fn new(frequencies: Vec<f64>) -> Result<FormantTarget, TractError> {
+        let target = FormantTarget {
+            frequencies,
+            bandwidths: None,
+        };
+        target.validate()?;
+        Ok(target)
+    }
+
+    pub fn with_bandwidths(
+        frequencies: Vec<f64>,
+        bandwidths: Vec<f64>,
+    ) -> Result<FormantTarget, TractError> {
+        let target = FormantTarget {
+            frequencies,
+            bandwidths: Some(bandwidths),
+        };
+        target.validate()?;
+        Ok(target)
+    }
+
+    /// Checks that there is at least one frequency, that the frequencies are finite, positive
+    /// and ascending, and that there is a finite, positive bandwidth for each frequency, if any.
+    pub fn validate(&self) -> Result<(), TractError> {
+        if self.frequencies.is_empty() {
+            return Err(TractError::EmptyTarget);
+        }
+        let mut previous = 0.0;
+        for (index, &frequency) in self.frequencies.iter().enumerate() {
+            if !(frequency.is_finite() && frequency > previous) {
+                return Err(TractError::InvalidFormant { index, frequency });
+            }
+            previous = frequency;
+        }
+
+        if let Some(bandwidths) = &self.bandwidths {
+            if bandwidths.len() != self.frequencies.len() {
+                return Err(TractError::LengthMismatch {
+                    expected: self.frequencies.len(),
+                    actual: bandwidths.len(),
+                });
+            }
+            if let Some(index) = bandwidths
+                .iter()
+                .position(|bandwidth| !(bandwidth.is_finite() && *bandwidth > 0.0))
+            {
+                return Err(TractError::InvalidBandwidth {
+                    index,
+                    bandwidth: bandwidths[index],
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Options controlling the inversion.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InversionOptions {
+    /// The number of cosine basis functions shaping the tract (higher is less smooth).
+    pub basis: usize,
+    /// The maximum number of iterations.
+    pub iterations: usize,
+    /// The weight of the smoothness penalty (higher stays closer to the resting shape).
+    pub smoothness: f64,
+    /// The error (semitones, root-mean-square) below which the fit is accepted.
+    pub tolerance: f64,
+}
+
+impl Default for InversionOptions {
+    fn default() -> InversionOptions {
+        InversionOptions {
+            basis: 6,
+            iterations: 40,
+            smoothness: 0.01,
+            tolerance: 0.1,
+        }
+    }
+}
+
+/// The outcome of an inversion.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InversionReport {
+    /// The formants of the fitted shape.
+    pub formants: Vec<Formant>,
+    /// The root-mean-square frequency error of the fitted shape (semitones).
+    pub error: f64,
+    /// The number of iterations performed.
+    pub iterations: usize,
+}
+
+impl<T: Float> Tract<T> {
+    /// Finds a smooth, plausible resting shape whose formants match the target, starting from
+    /// the current resting shape, and sets it as the tract's resting shape.
+    ///
+    /// The glottal section of the tract is left unchanged.
+    pub fn fit_formants(
+        &mut self,
+        target: &FormantTarget,
+        options: &InversionOptions,
+    ) -> Result<InversionReport, TractError> {
+        target.validate()?;
+        let mut inversion = Inversion::new(self, target, options);
+        let mut coefficients = vec![0.0; options.basis];
+        let (mut residuals, mut formants) = inversion.residuals(&coefficients);
+        let mut cost = sum_of_squares(&residuals);
+        let mut damping = 1e-2;
+        let mut iterations = 0;
+
+        while iterations < options.iterations && inversion.error(&formants) > options.tolerance {
+            iterations += 1;
+
+            // Estimate the sensitivity of each residual to each coefficient
+            let jacobian: Vec<Vec<f64>> = (0..options.basis)
+                .map(|j| {
+                    let mut probe = coefficients.clone();
+                    probe[j] += SENSITIVITY_STEP;
+                    let (perturbed, _) = inversion.residuals(&probe);
+                    perturbed
+                        .iter()
+                        .zip(residuals.iter())
+                        .map(|(a, b)| (a - b) / SENSITIVITY_STEP)
+                        .collect()
+                })
+                .collect();
+
+            // Damp the step until it reduces the cost
+            let mut improved = false;
+            while damping < 1e6 {
+                let step = solve_damped(&jacobian, &residuals, damping);
+                let candidate: Vec<f64> = coefficients
+                    .iter()
+                    .zip(step.iter())
+                    .map(|(c, s)| c + s)
+                    .collect();
+                let (candidate_residuals, candidate_formants) = inversion.residuals(&candidate);
+                let candidate_cost = sum_of_squares(&candidate_residuals);
+
+                if candidate_cost < cost {
+                    coefficients = candidate;
+                    residuals = candidate_residuals;
+                    formants = candidate_formants;
+                    cost = candidate_cost;
+                    damping = (damping / 10.0).max(1e-6);
+                    improved = true;
+                    break;
+                }
+                damping *= 10.0;
+            }
+            if !improved {
+                break;
+            }
+        }
+
+        let diameters = inversion.diameters(&coefficients);
+        let error = inversion.error(&formants);
+        self.oral_diameter_rest.copy_from_slice(&diameters);
+        self.oral_diameter_target.copy_from_slice(&diameters);
+
+        Ok(InversionReport {
+            formants,
+            error,
+            iterations,
+        })
+    }
+}
+
+/// The state of an inversion in progress.
+struct Inversion<'a, T: Float> {
+    target: &'a FormantTarget,
+    smoothness: f64,
+    /// A copy of the tract used to evaluate candidate shapes.
+    probe: Tract<T>,
+    /// The shape about which candidates are perturbed.
+    rest: Vec<f64>,
+    /// The first section that may be reshaped (beyond the glottis).
+    start: usize,
+    frequencies: Vec<f64>,
+}
+
+impl<'a, T: Float> Inversion<'a, T> {
+    fn new(
+        tract: &Tract<T>,
+        target: &'a FormantTarget,
+        options: &InversionOptions,
+    ) -> Inversion<'a, T> {
+        let max_frequency = SEARCH_MAX_FREQUENCY.min(tract.sample_rate / 2.0);
+        Inversion {
+            target,
+            smoothness: options.smoothness,
+            probe: tract.clone(),
+            rest: tract.oral_diameter_rest.clone(),
+            start: tract.oral_length / 6,
+            frequencies: (1..)
+                .map(|i| i as f64 * SEARCH_RESOLUTION)
+                .take_while(|&frequency| frequency < max_frequency)
+                .collect(),
+        }
+    }
+
+    /// Returns the diameters perturbed from rest by the basis coefficients.
+    fn diameters(&self, coefficients: &[f64]) -> Vec<f64> {
+        let span = (self.rest.len() - self.start) as f64;
+        self.rest
+            .iter()
+            .enumerate()
+            .map(|(m, &diameter)| {
+                if m < self.start {
+                    return diameter;
+                }
+                let x = (m - self.start) as f64 / span;
+                let perturbation: f64 = coefficients
+                    .iter()
+                    .enumerate()
+                    .map(|(j, c)| c * ((j + 1) as f64 * PI * x).cos())
+                    .sum();
+                (diameter.max(MIN_DIAMETER) * perturbation.exp()).clamp(MIN_DIAMETER, MAX_DIAMETER)
+            })
+            .collect()
+    }
+
+    /// Returns the residuals (formant errors and smoothness penalties) of a candidate shape.
+    fn residuals(&mut self, coefficients: &[f64]) -> (Vec<f64>, Vec<Formant>) {
+        let diameters = self.diameters(coefficients);
+        self.probe.oral.diameter.copy_from_slice(&diameters);
+        self.probe.calculate_oral_reflections();
+        let magnitudes = self.probe.frequency_response(&self.frequencies);
+        let formants = find_formants(&self.frequencies, &magnitudes);
+
+        let mut residuals = Vec::new();
+        for (i, &frequency) in self.target.frequencies.iter().enumerate() {
+            residuals.push(match formants.get(i) {
+                Some(formant) => semitones(formant.frequency, frequency),
+                None => MISSING_FORMANT_PENALTY,
+            });
+        }
+        if let Some(bandwidths) = &self.target.bandwidths {
+            for (i, &bandwidth) in bandwidths.iter().enumerate() {
+                residuals.push(match formants.get(i) {
+                    Some(formant) => BANDWIDTH_WEIGHT * semitones(formant.bandwidth, bandwidth),
+                    None => MISSING_FORMANT_PENALTY,
+                });
+            }
+        }
+
+        // Penalise higher-order (less smooth) perturbations more heavily
+        let weight = self.smoothness.sqrt();
+        for (j, c) in coefficients.iter().enumerate() {
+            residuals.push(weight * (j + 1) as f64 * c);
+        }
+
+        (residuals, formants)
+    }
+
+    /// Returns the root-mean-square frequency error of a set of formants (semitones).
+    fn error(&self, formants: &[Formant]) -> f64 {
+        let frequencies = &self.target.frequencies;
+        let sum: f64 = frequencies
+            .iter()
+            .enumerate()
+            .map(|(i, &frequency)| match formants.get(i) {
+                Some(formant) => semitones(formant.frequency, frequency).powi(2),
+                None => MISSING_FORMANT_PENALTY.powi(2),
+            })
+            .sum();
+        (sum / frequencies.len().max(1) as f64).sqrt()
+    }
+}
+
+/// Returns the interval between two frequencies in semitones.
+fn semitones(frequency: f64, target: f64) -> f64 {
+    12.0 * (frequency / target).log2()
+}
+
+fn sum_of_squares(values: &[f64]) -> f64 {
+    values.iter().map(|value| value * value).sum()
+}
+
+/// Solves (JᵀJ + λ·diag(JᵀJ))·x = -Jᵀr for the Levenberg-Marquardt step.
+///
+/// `jacobian` is indexed by coefficient, then residual.
+fn solve_damped(jacobian: &[Vec<f64>], residuals: &[f64], damping: f64) -> Vec<f64> {
+    let n = jacobian.len();
+    let dot = |a: &[f64], b: &[f64]| a.iter().zip(b.iter()).map(|(x, y)| x * y).sum::<f64>();
+
+    // Augmented normal equations [A | b]
+    let mut matrix: Vec<Vec<f64>> = (0..n)
+        .map(|i| {
+            let mut row: Vec<f64> = (0..n).map(|j| dot(&jacobian[i], &jacobian[j])).collect();
+            row[i] += damping * row[i].max(1e-9);
+            row.push(-dot(&jacobian[i], residuals));
+            row
+        })
+        .collect();
+
+    // Gaussian elimination with partial pivoting
+    for column in 0..n {
+        let pivot = (column..n)
+            .max_by(|&a, &b| matrix[a][column].abs().total_cmp(&matrix[b][column].abs()))
+            .unwrap();
+        matrix.swap(column, pivot);
+        if matrix[column][column].abs() < 1e-12 {
+            continue;
+        }
+        let pivot_row = matrix[column].clone();
+        for row in matrix.iter_mut().skip(column + 1) {
+            let factor = row[column] / pivot_row[column];
+            for (value, pivot) in row[column..].iter_mut().zip(&pivot_row[column..]) {
+                *value -= factor * pivot;
+            }
+        }
+    }
+
+    let mut solution = vec![0.0; n];
+    for row in (0..n).rev() {
+        if matrix[row][row].abs() < 1e-12 {
+            continue;
+        }
+        let sum: f64 = (row + 1..n).map(|k| matrix[row][k] * solution[k]).sum();
+        solution[row] = (matrix[row][n] - sum) / matrix[row][row];
+    }
+
+    solution
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solve_damped() {
+        // J = I, r = [1, -2] => x = -r / (1 + λ)
+        let jacobian = vec![vec![1.0, 0.0], vec![0.0, 1.0]];
+        let solution = solve_damped(&jacobian, &[1.0, -2.0], 0.0);
+        assert!((solution[0] + 1.0).abs() < 1e-9);
+        assert!((solution[1] - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fit_formants() {
+        let mut tract = Tract::new(44, 28);
+        tract.init();
+        tract.post_block();
+        let target = FormantTarget::new(vec![300.0, 2200.0]).unwrap();
+        let options = InversionOptions {
+            iterations: 15,
+            ..InversionOptions::default()
+        };
+
+        let report = tract.fit_formants(&target, &options).unwrap();
+        assert!(report.error < 1.0);
+        assert!(tract.oral_diameter_rest[tract.oral_length / 6..]
+            .iter()
+            .all(|d| (MIN_DIAMETER..=MAX_DIAMETER).contains(d)));
+    }
+
+    #[test]
+    fn test_validate_target() {
+        assert_eq!(
+            FormantTarget::new(vec![300.0, 0.0]),
+            Err(TractError::InvalidFormant {
+                index: 1,
+                frequency: 0.0
+            })
+        );
+        assert!(FormantTarget::new(vec![-300.0]).is_err());
+        assert!(FormantTarget::new(vec![f64::NAN]).is_err());
+        assert!(FormantTarget::new(vec![2200.0, 300.0]).is_err());
+        assert_eq!(
+            FormantTarget::with_bandwidths(vec![300.0, 2200.0], vec![60.0]),
+            Err(TractError::LengthMismatch {
+                expected: 2,
+                actual: 1
+            })
+        );
+        assert!(FormantTarget::with_bandwidths(vec![300.0], vec![f64::NAN]).is_err());
+        assert_eq!(FormantTarget::new(Vec::new()), Err(TractError::EmptyTarget));
+
+        // targets built directly are checked when fitting
+        let mut tract = Tract::new(44, 28);
+        let target = FormantTarget {
+            frequencies: vec![f64::NAN],
+            bandwidths: None,
+        };
+        assert!(tract
+            .fit_formants(&target, &InversionOptions::default())
+            .is_err());
+    }
+}
@@ -1,117 +1,181 @@
+pub mod analysis;
+pub mod area_function;
+pub mod builder;
 mod cavity;
+pub mod consonants;
+pub mod error;
+pub mod inversion;
+pub mod snapshot;
+mod subglottis;
 mod utils;
 mod velum;
+pub mod vowels;
 
+use crate::filter::stateful::decimator::{Decimator, Oversampling, MAX_OVERSAMPLING};
+use crate::float::Float;
+use area_function::AreaFunction;
+pub use builder::TractBuilder;
+use builder::MIN_CAVITY_LENGTH;
 use cavity::Cavity;
-use utils::{circle_area, ease, kelly_lochbaum, min};
+use consonants::{Constriction, GestureFrame};
+use error::{validate_diameters, TractError};
+pub use snapshot::TractSnapshot;
+use std::f64::consts::PI;
+use subglottis::{Subglottis, GLOTTAL_AREA_MAX};
+use utils::{circle_area, ease, kelly_lochbaum, move_towards};
 use velum::Velum;
+use vowels::{Articulation, Vowel};
 
 /// Number of sections in the oral cavity.
 // const ORAL_LENGTH: usize = 44;
 /// Number of sections in the oral cavity.
 // const NASAL_LENGTH: usize = 28;
-/// Coefficient of reflection at the glottis.
+/// Default coefficient of reflection at the glottis.
 const K_GLOTTAL: f64 = 0.7;
-/// Coefficient of reflection at the labia.
+/// Default coefficient of reflection at the labia.
 const K_LABIAL: f64 = -0.85;
-/// Coefficient of reflection at the nose.
+/// Default coefficient of reflection at the nose.
 const K_NOSE: f64 = -0.9;
 /// Coefficient of reflection pertaining to the soft palate.
 const K_SOFT_PALATE: f64 = 1.0;
 /// Coefficient of reflection pertaining to the hard palate.
 const K_HARD_PALATE: f64 = 0.9;
-/// The index of the buccal segment that connects the pharyngeal and nasal cavities.
+/// The default index of the buccal segment that connects the pharyngeal and nasal cavities.
 const VELUM_INDEX: usize = 17;
-/// The coefficient of sonic attenutation.
+/// The default coefficient of sonic attenutation.
 const ATTENUATION: f64 = 0.9999;
 /// The glottal diameter relative to the maximum oral diameter.
 const RATIO_GLOTTAL_DIAMETER: f64 = 0.16667; // (1/6)^2
 /// The pharyngeal diameter relative to the maximum oral diameter.
 const RATIO_PHARYNGEAL_DIAMETER: f64 = 2.0 / 3.0;
+/// The rate at which the oral cavity moves towards its target diameter (diameter per second).
+const MOVEMENT_SPEED: f64 = 15.0;
+/// The rate at which the velum opens and closes (diameter per second).
+const VELUM_SPEED: f64 = 1.5;
+/// The nasal area below which the velum is considered closed.
+const VELUM_CLOSED_AREA: f64 = 0.05;
+/// The initial amplitude of a transient caused by the release of a closure.
+const TRANSIENT_STRENGTH: f64 = 0.3;
+/// The rate of exponential decay of transients (halvings per second).
+const TRANSIENT_EXPONENT: f64 = 200.0;
+/// Amplitude below which transients are discarded.
+const TRANSIENT_THRESHOLD: f64 = 1e-4;
+/// Output magnitude above which the tract is considered unstable.
+const OVERFLOW_THRESHOLD: f64 = 100.0;
+/// The maximum number of samples between reshapes of the tract during block processing.
+pub const BLOCK_SIZE: usize = 64;
+
+/// A transient impulse released from a closure in the oral cavity.
+#[derive(Clone, Debug)]
+struct Transient<T: Float> {
+    /// The section at which the closure was released.
+    index: usize,
+    /// The current amplitude of the impulse.
+    amplitude: T,
+}
+
+/// Returns the neutral diameter of each section of an oral cavity of `oral_length` sections.
+fn neutral_diameters(oral_length: usize) -> Vec<f64> {
+    let mut diameters = vec![0.0; oral_length];
+    write_neutral_diameters(&mut diameters);
+    diameters
+}
+
+/// Writes the neutral diameter of each section of an oral cavity, in place.
+fn write_neutral_diameters(diameters: &mut [f64]) {
+    let oral_length = diameters.len();
+    let oral_diameter = 3.0;
+    let glottal_end = oral_length as f64 / 6.0;
+    let glottal_diameter = oral_diameter * RATIO_GLOTTAL_DIAMETER;
+    let pharyngeal_end = oral_length as f64 / 3.0;
+    let pharyngeal_diameter = oral_diameter * RATIO_PHARYNGEAL_DIAMETER;
+    let glottal_difference = pharyngeal_diameter - glottal_diameter;
+
+    // Generate oral cavity shape / diameter
+    for (m, diameter) in diameters.iter_mut().enumerate() {
+        *diameter = if (m as f64) < glottal_end {
+            glottal_diameter + ease((m as f64) / glottal_end) * glottal_difference
+        } else if (m as f64) < pharyngeal_end {
+            pharyngeal_diameter
+        } else {
+            oral_diameter
+        };
+    }
+}
 
 /// A stateful vocal tract filter.
 ///
 /// Implements a 1-dimensional abstraction of a 2-dimensional digital wave-guide model.
+///
+/// The shape of the tract is described in `f64` whereas the wave-guide runs in precision `T`.
+///
+/// A voice (a [`Glottis`](crate::source::Glottis) and a 44/28 section tract) costs roughly 2% of
+/// a single x86-64 core at 44.1kHz, about 50 voices in real time, of which the tract is about
+/// half. Measure on the target machine with `cargo run --release --example tract_cost`.
 #[derive(Clone, Debug)]
-pub struct Tract {
+pub struct Tract<T: Float = f64> {
     /// The number of sections in the oral cavity.
     oral_length: usize,
     /// The number of sections in the nasal cavity.
     nasal_length: usize,
     /// The tract's oral cavity (comprised of glottal, pharyngeal and buccal sections).
-    oral: Cavity,
+    oral: Cavity<T>,
     /// The tract's nasal cavity (static once initalised).
-    nasal: Cavity,
+    nasal: Cavity<T>,
     /// The tract's velum (located at the nasopharyngeal junction).
-    velum: Velum,
+    velum: Velum<T>,
+    /// The index of the oral section at which the nasal cavity branches.
+    velum_index: usize,
+    /// Coefficient of reflection at the glottis.
+    k_glottal: T,
+    /// Coefficient of reflection at the labia.
+    k_labial: T,
+    /// Coefficient of reflection at the nose.
+    k_nose: T,
+    /// The coefficient of sonic attenuation applied in each section.
+    attenuation: T,
     /// The tract's rest diameter for current tongue position.
     oral_diameter_rest: Vec<f64>,
     /// The tract's target diameter for current tongue position and target phoneme.
     oral_diameter_target: Vec<f64>,
     /// A list of alive transient impulses within the tract (used to form certain consonants).
-    transients: Vec<f64>,
+    transients: Vec<Transient<T>>,
     /// The index of the most recent closure/obstruction within the oral cavity (obstructions cause consonants).
-    last_obstruction: i8,
+    last_obstruction: Option<usize>,
+    /// The current constriction of the oral cavity (formed by consonants).
+    constriction: Option<Constriction>,
+    /// The sample rate at which the tract is processed.
+    sample_rate: f64,
+    /// The optional subglottal tract, coupled to the oral cavity through the glottis.
+    subglottis: Option<Subglottis<T>>,
+    /// The current glottal opening in range [0-1].
+    glottal_opening: f64,
+    /// The most recent error from which the tract recovered (cleared by [`Tract::take_error`]).
+    error: Option<TractError>,
+    /// The number of times the tract has recovered from an error.
+    recoveries: usize,
+    /// Filters the wave-guide's steps down to the sample rate.
+    decimator: Decimator<T>,
 }
 
 impl Tract {
-    /// Creates a new vocal tract filter with default values.
+    /// Creates a new `f64` vocal tract filter with default values.
+    ///
+    /// Use [`TractBuilder::build`] for other precisions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lengths are invalid (see [`TractBuilder`] for fallible construction).
     pub fn new(oral_length: usize, nasal_length: usize) -> Tract {
-        let mut oral = Cavity::new(oral_length);
-        let mut nasal = Cavity::new(nasal_length);
-        let velum = Velum::new(0.04, 0.1);
-
-        // Initalialise oral cavity
-        let oral_diameter = 3.0;
-        let glottal_end = oral_length as f64 / 6.0;
-        let glottal_diameter = oral_diameter * RATIO_GLOTTAL_DIAMETER;
-        let pharyngeal_end = oral_length as f64 / 3.0;
-        let pharyngeal_diameter = oral_diameter * RATIO_PHARYNGEAL_DIAMETER;
-        let glottal_difference = pharyngeal_diameter - glottal_diameter;
-        let mut oral_diameter_rest = vec![0.0; oral_length];
-        let mut oral_diameter_target = vec![0.0; oral_length];
-
-        // Generate oral cavity shape / diameter
-        for m in 0..oral_length {
-            let diameter = if (m as f64) < glottal_end {
-                glottal_diameter + ease((m as f64) / glottal_end) * glottal_difference
-            } else if (m as f64) < pharyngeal_end {
-                pharyngeal_diameter
-            } else {
-                oral_diameter
-            };
-
-            oral.diameter[m] = diameter;
-            oral_diameter_rest[m] = diameter;
-            oral_diameter_target[m] = diameter;
-        }
-
-        // Initalialise nasal cavity
-        // TODO try and get rid of magic numbers if possible
-        for i in 0..nasal_length {
-            let d = 2.0 * (i as f64 / nasal_length as f64);
-            let diameter = if d < 1.0 {
-                0.4 + (1.6 * d)
-            } else {
-                0.2 + 1.2 * (2.0 - d)
-            };
-            nasal.diameter[i] = min(diameter, 1.2) * nasal_length as f64 / 28.0
-        }
-
-        // Construct
-        Tract {
-            oral_length,
-            nasal_length,
-            oral,
-            nasal,
-            velum,
-            oral_diameter_rest,
-            oral_diameter_target,
-            transients: vec![0.0; oral_length],
-            last_obstruction: -1,
-        }
+        TractBuilder::new()
+            .oral_length(oral_length)
+            .nasal_length(nasal_length)
+            .build()
+            .expect("invalid tract configuration")
     }
+}
 
+impl<T: Float> Tract<T> {
     /// Initialises the vocal tract.
     pub fn init(&mut self) {
         self.calculate_oral_reflections();
@@ -131,93 +195,578 @@ impl Tract {
             // let coefficient = if m > this.pharynxEnd {K_HARD_PALATE} else {K_SOFT_PALATE}
             let coefficient = K_SOFT_PALATE;
             // prevent error if 0
-            oral.k[m] = if oral.area[m] == 0.0 {
+            oral.k[m] = T::from_f64(if oral.area[m - 1] + oral.area[m] == 0.0 {
                 ATTENUATION
             } else {
                 kelly_lochbaum(oral.area[m - 1], oral.area[m]) * coefficient
-            }
+            })
         }
 
         // now at velopharyngeal junction / port
-        let sum = oral.area[VELUM_INDEX] + oral.area[VELUM_INDEX + 1] + nasal.area[0];
-        self.velum.k_left = (2.0 * oral.area[VELUM_INDEX] - sum) / sum;
-        self.velum.k_right = (2.0 * oral.area[VELUM_INDEX + 1] - sum) / sum;
-        self.velum.k_nose = (2.0 * nasal.area[0] - sum) / sum;
+        let velum_index = self.velum_index;
+        let sum = oral.area[velum_index] + oral.area[velum_index + 1] + nasal.area[0];
+        if sum == 0.0 {
+            // fully closed, so reflect everything
+            self.velum.k_left = T::one();
+            self.velum.k_right = T::one();
+            self.velum.k_nose = T::one();
+            return;
+        }
+        self.velum.k_left = T::from_f64((2.0 * oral.area[velum_index] - sum) / sum);
+        self.velum.k_right = T::from_f64((2.0 * oral.area[velum_index + 1] - sum) / sum);
+        self.velum.k_nose = T::from_f64((2.0 * nasal.area[0] - sum) / sum);
     }
 
     /// Calculates the coefficients of reflection for each junction in the nasal cavity.
     fn calculate_nasal_reflections(&mut self) {
-        for m in 0..self.nasal_length {
-            self.nasal.area[m] = circle_area(self.nasal.diameter[m])
+        self.nasal.calculate_reflections();
+    }
+
+    /// Sets the resting shape of the oral cavity from the position of the articulators.
+    pub fn set_articulation(&mut self, articulation: &Articulation) {
+        articulation.write_diameters(&mut self.oral_diameter_rest);
+        self.oral_diameter_target
+            .copy_from_slice(&self.oral_diameter_rest);
+    }
+
+    /// Sets the resting shape of the oral cavity to that of a vowel.
+    pub fn set_vowel(&mut self, vowel: Vowel) {
+        self.set_articulation(&vowel.articulation());
+    }
+
+    /// Sets the target shape of the oral cavity from an area function.
+    ///
+    /// The area function is stretched to span the whole oral cavity, from glottis to lips.
+    pub fn set_area_function(&mut self, area_function: &AreaFunction) -> Result<(), TractError> {
+        self.set_diameters(&area_function.diameters(self.oral_length))
+    }
+
+    /// Sets the resting shape of the oral cavity from the diameter of each section.
+    pub fn set_diameters(&mut self, diameters: &[f64]) -> Result<(), TractError> {
+        if diameters.len() != self.oral_length {
+            return Err(TractError::LengthMismatch {
+                expected: self.oral_length,
+                actual: diameters.len(),
+            });
         }
-        for m in 1..self.nasal_length {
-            self.nasal.k[m] = kelly_lochbaum(self.nasal.area[m - 1], self.nasal.area[m])
+        validate_diameters(diameters)?;
+        self.oral_diameter_rest.copy_from_slice(diameters);
+        self.oral_diameter_target.copy_from_slice(diameters);
+        Ok(())
+    }
+
+    /// Sets the sample rate at which the tract is processed.
+    pub fn set_sample_rate(&mut self, sample_rate: f64) {
+        self.sample_rate = sample_rate;
+    }
+
+    /// Sets the current constriction of the oral cavity, narrowing it about the resting shape.
+    pub fn set_constriction(&mut self, constriction: Option<Constriction>) {
+        self.constriction = constriction;
+    }
+
+    /// Sets the opening of the velum in range [0-1] (0 being closed).
+    pub fn set_velum_opening(&mut self, opening: f64) {
+        self.velum.target_diameter = opening.clamp(0.0, 1.0) * self.velum.max_diameter;
+    }
+
+    /// Applies the constriction and velum of a consonant gesture to the tract.
+    ///
+    /// The frame's voicing should be applied to the glottal source by the caller.
+    pub fn articulate(&mut self, frame: &GestureFrame) {
+        self.set_constriction(frame.constriction);
+        self.set_velum_opening(frame.velum);
+    }
+
+    /// Moves the oral cavity and velum towards their targets over `delta_time` seconds.
+    ///
+    /// Reflections are recalculated by [`Tract::post_block`].
+    pub fn reshape(&mut self, delta_time: f64) {
+        self.calculate_target_diameters();
+
+        // Closures are formed faster than they are released
+        let amount = delta_time * MOVEMENT_SPEED;
+        let mut obstruction = None;
+        for m in 0..self.oral_length {
+            let diameter = &mut self.oral.diameter[m];
+            *diameter = move_towards(
+                *diameter,
+                self.oral_diameter_target[m],
+                amount,
+                2.0 * amount,
+            )
+            .max(0.0);
+            if *diameter <= 0.0 {
+                obstruction = Some(m);
+            }
         }
+
+        // Release a transient when a closure opens (unless the velum is open)
+        if let (Some(index), None) = (self.last_obstruction, obstruction) {
+            if self.nasal.area[0] < VELUM_CLOSED_AREA {
+                self.transients.push(Transient {
+                    index,
+                    amplitude: T::from_f64(TRANSIENT_STRENGTH),
+                });
+            }
+        }
+        self.last_obstruction = obstruction;
+
+        let amount = delta_time * VELUM_SPEED;
+        self.nasal.diameter[0] = move_towards(
+            self.nasal.diameter[0],
+            self.velum.target_diameter,
+            amount,
+            amount,
+        );
     }
 
-    /// Simulates the propogation of sound within the vocal tract (must run at twice the sample rate).
-    fn step(&mut self, excitation: f64, _noise: f64) {
-        // TODO process turnbulance noise
-        // let noise_output = noise
+    /// Calculates the target diameter from the rest diameter and the current constriction.
+    fn calculate_target_diameters(&mut self) {
+        self.oral_diameter_target
+            .copy_from_slice(&self.oral_diameter_rest);
+
+        let constriction = match self.constriction {
+            Some(constriction) => constriction,
+            None => return,
+        };
+
+        // The tongue tip forms narrower constrictions than the tongue body
+        let scale = self.oral_length as f64 / 44.0;
+        let index = constriction.index * 44.0;
+        let width = if index < 25.0 {
+            10.0
+        } else if index >= 32.0 {
+            5.0
+        } else {
+            10.0 - 5.0 * (index - 25.0) / 7.0
+        } * scale;
+        let index = constriction.index * self.oral_length as f64;
+
+        let start = (index - width).ceil().max(0.0) as usize;
+        let end = ((index + width) as usize + 1).min(self.oral_length);
+        for m in start..end {
+            let position = (m as f64 - index).abs() - 0.5;
+            let shrink = if position <= 0.0 {
+                0.0
+            } else if position > width {
+                1.0
+            } else {
+                0.5 * (1.0 - (PI * position / width).cos())
+            };
+
+            let target = &mut self.oral_diameter_target[m];
+            if constriction.diameter < *target {
+                *target = constriction.diameter + (*target - constriction.diameter) * shrink;
+            }
+        }
+    }
+
+    /// Adds turbulence noise at the current constriction, scaled by how narrow it is.
+    fn add_turbulence_noise(&mut self, noise: T) {
+        let constriction = match self.constriction {
+            Some(constriction) if constriction.fricative_intensity > 0.0 => constriction,
+            _ => return,
+        };
+
+        let index = constriction.index * self.oral_length as f64;
+        let i = index.floor() as usize;
+        if i + 2 >= self.oral_length {
+            return;
+        }
+
+        // Turbulence only occurs when the constriction is narrow but open
+        let diameter = self.oral.diameter[i + 1];
+        let thinness = (8.0 * (1.4 - diameter) / 2.0).clamp(0.0, 1.0);
+        let openness = (30.0 * (diameter - 0.6) / 2.0).clamp(0.0, 1.0);
+        let noise = noise * T::from_f64(constriction.fricative_intensity * thinness * openness);
+
+        let delta = index - i as f64;
+        let (noise0, noise1) = (
+            noise * T::from_f64((1.0 - delta) / 2.0),
+            noise * T::from_f64(delta / 2.0),
+        );
+        self.oral.right[i + 1] += noise0;
+        self.oral.left[i + 1] += noise0;
+        self.oral.right[i + 2] += noise1;
+        self.oral.left[i + 2] += noise1;
+    }
+
+    /// Adds and decays each alive transient.
+    fn process_transients(&mut self) {
+        if self.transients.is_empty() {
+            return;
+        }
+        let decay = T::from_f64((-TRANSIENT_EXPONENT / self.step_rate()).exp2());
+        let half = T::from_f64(0.5);
+        for transient in self.transients.iter_mut() {
+            self.oral.right[transient.index] += transient.amplitude * half;
+            self.oral.left[transient.index] += transient.amplitude * half;
+            transient.amplitude *= decay;
+        }
+        let threshold = T::from_f64(TRANSIENT_THRESHOLD);
+        self.transients
+            .retain(|transient| transient.amplitude > threshold);
+    }
+
+    /// Couples a subglottal tract (trachea and lungs) of `length` sections to the glottal end.
+    ///
+    /// Once coupled, the glottal reflection varies with the glottal opening
+    /// (see [`Tract::set_glottal_opening`]) rather than remaining fixed.
+    pub fn enable_subglottis(&mut self, length: usize) -> Result<(), TractError> {
+        if length < MIN_CAVITY_LENGTH {
+            return Err(TractError::InvalidLength {
+                cavity: "subglottal",
+                length,
+            });
+        }
+        self.subglottis = Some(Subglottis::new(length));
+        Ok(())
+    }
 
+    /// Decouples the subglottal tract, restoring a fixed glottal reflection.
+    pub fn disable_subglottis(&mut self) {
+        self.subglottis = None;
+    }
+
+    /// Sets the current glottal opening in range [0-1] (0 being fully adducted).
+    pub fn set_glottal_opening(&mut self, opening: f64) {
+        self.glottal_opening = opening.clamp(0.0, 1.0);
+    }
+
+    /// Returns the number of wave-guide steps per sample (see [`TractBuilder::oversampling`]).
+    pub fn oversampling(&self) -> Oversampling {
+        self.decimator.oversampling()
+    }
+
+    /// Returns the rate at which the wave-guide steps (hz).
+    fn step_rate(&self) -> f64 {
+        self.oversampling().factor() as f64 * self.sample_rate
+    }
+
+    /// Simulates the propogation of sound within the vocal tract (runs once per oversampled step).
+    fn step(&mut self, excitation: T, noise: T) {
         let oral = &mut self.oral;
         let nasal = &mut self.nasal;
         let velum = &mut self.velum;
 
         // Calculate reflections in the buccal cavity.
         // Glottal excitation enters left and labial reflection enters right
-        oral.j_right[0] = oral.left[0] * K_GLOTTAL + excitation;
-        oral.j_left[self.oral_length] = oral.right[self.oral_length - 1] * K_LABIAL;
+        match &mut self.subglottis {
+            Some(subglottis) => {
+                // The glottal reflection depends upon the glottal opening
+                let glottal_area = self.glottal_opening * GLOTTAL_AREA_MAX;
+                let (k_glottal, transmitted) =
+                    subglottis.couple(oral.area[0], oral.left[0], glottal_area);
+                oral.j_right[0] = oral.left[0] * k_glottal + transmitted + excitation;
+                subglottis.step(self.attenuation);
+            }
+            None => oral.j_right[0] = oral.left[0] * self.k_glottal + excitation,
+        }
+        oral.j_left[self.oral_length] = oral.right[self.oral_length - 1] * self.k_labial;
 
         // Reflection (w) at each junction
-        for m in 1..self.oral_length {
-            let w = oral.k[m] * (oral.right[m - 1] + oral.left[m]); // reflection
-            oral.j_right[m] = oral.right[m - 1] - w;
-            oral.j_left[m] = oral.left[m] + w;
-        }
+        oral.scatter();
 
         // Calculate reflections at the velopharyngeal junction
-        oral.j_left[VELUM_INDEX] = velum.k_left * oral.right[VELUM_INDEX - 1]
-            + (1.0 + velum.k_left) * (nasal.left[0] + oral.left[VELUM_INDEX]);
-        oral.j_right[VELUM_INDEX] = velum.k_right * oral.left[VELUM_INDEX]
-            + (1.0 + velum.k_right) * (oral.right[VELUM_INDEX - 1] + nasal.left[0]);
+        let v = self.velum_index;
+        let one = T::one();
+        oral.j_left[v] = velum.k_left * oral.right[v - 1]
+            + (one + velum.k_left) * (nasal.left[0] + oral.left[v]);
+        oral.j_right[v] = velum.k_right * oral.left[v]
+            + (one + velum.k_right) * (oral.right[v - 1] + nasal.left[0]);
         nasal.j_right[0] = velum.k_nose * nasal.left[0]
-            + (1.0 + velum.k_nose) * (oral.left[VELUM_INDEX] + oral.right[VELUM_INDEX - 1]);
+            + (one + velum.k_nose) * (oral.left[v] + oral.right[v - 1]);
 
         // Transfer attenuated energy in oral cavity
-        for m in 0..self.oral_length {
-            oral.right[m] = oral.j_right[m] * ATTENUATION;
-            oral.left[m] = oral.j_left[m + 1] * ATTENUATION;
-        }
+        oral.propagate(self.attenuation);
 
         // Calculate reflection at the nose
-        nasal.j_left[self.nasal_length] = nasal.right[self.nasal_length - 1] * K_NOSE;
+        nasal.j_left[self.nasal_length] = nasal.right[self.nasal_length - 1] * self.k_nose;
 
         // Calculate reflection (w) for each section (m) in nasal cavity
-        for m in 1..self.nasal_length {
-            let w = nasal.k[m] * (nasal.right[m - 1] + nasal.left[m]);
-            nasal.j_right[m] = nasal.right[m - 1] - w;
-            nasal.j_left[m] = nasal.left[m] + w;
-        }
+        nasal.scatter();
 
         // Attenuate each section (m) in nasal cavity
-        for m in 0..self.nasal_length {
-            nasal.right[m] = nasal.j_right[m] * ATTENUATION;
-            nasal.left[m] = nasal.j_left[m + 1] * ATTENUATION;
+        nasal.propagate(self.attenuation);
+
+        self.process_transients();
+        self.add_turbulence_noise(noise);
+    }
+
+    /// Silences the tract, discarding all sound within it without changing its shape.
+    pub fn reset(&mut self) {
+        self.oral.reset();
+        self.nasal.reset();
+        if let Some(subglottis) = &mut self.subglottis {
+            subglottis.cavity.reset();
         }
+        self.transients.clear();
+        self.decimator.reset();
+    }
+
+    /// Returns and clears the most recent error from which the tract recovered.
+    pub fn take_error(&mut self) -> Option<TractError> {
+        self.error.take()
+    }
+
+    /// Returns the number of times the tract has recovered from an error.
+    pub fn recoveries(&self) -> usize {
+        self.recoveries
+    }
+
+    /// Records an error and returns the tract to a safe, silent state.
+    fn recover(&mut self, error: TractError) {
+        if let TractError::InvalidDiameter { .. } = error {
+            // the shape can't be trusted so fall back to the neutral shape
+            self.oral_diameter_rest = neutral_diameters(self.oral_length);
+            self.oral_diameter_target
+                .copy_from_slice(&self.oral_diameter_rest);
+            self.oral.diameter.copy_from_slice(&self.oral_diameter_rest);
+            self.calculate_oral_reflections();
+        }
+        self.reset();
+        self.error = Some(error);
+        self.recoveries += 1;
     }
 
     pub fn pre_block(&mut self) {}
     pub fn post_block(&mut self) {
-        self.calculate_oral_reflections();
+        self.calculate_nasal_reflections();
+        match validate_diameters(&self.oral.diameter) {
+            Ok(()) => self.calculate_oral_reflections(),
+            Err(error) => self.recover(error),
+        }
+    }
+
+    /// Filters a block of excitation in place (see [`Tract::process_block_with`]).
+    pub fn process_block(&mut self, buffer: &mut [T]) {
+        self.process_block_with(buffer, None, None);
+    }
+
+    /// Filters a block of excitation in place, with optional turbulence noise and glottal
+    /// opening for each sample.
+    ///
+    /// The block is split into sub-blocks of at most [`BLOCK_SIZE`] samples. The tract is
+    /// reshaped towards its targets before each sub-block and its reflections glide to the new
+    /// shape across the sub-block, so hosts need not call the block hooks themselves. Without
+    /// a glottal opening, the most recent one set is held for the block.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `noise` or `glottal_opening` is shorter than `buffer`.
+    pub fn process_block_with(
+        &mut self,
+        buffer: &mut [T],
+        noise: Option<&[T]>,
+        glottal_opening: Option<&[f64]>,
+    ) {
+        let length = buffer.len();
+        assert!(
+            noise.map_or(length, <[T]>::len) >= length,
+            "noise buffer is too short"
+        );
+        assert!(
+            glottal_opening.map_or(length, <[f64]>::len) >= length,
+            "opening buffer is too short"
+        );
+
+        for (block, chunk) in buffer.chunks_mut(BLOCK_SIZE).enumerate() {
+            let start = block * BLOCK_SIZE;
+            self.begin_block(chunk.len());
+            for (i, sample) in chunk.iter_mut().enumerate() {
+                self.oral.interpolate();
+                self.nasal.interpolate();
+                self.velum.interpolate();
+                if let Some(opening) = glottal_opening {
+                    self.set_glottal_opening(opening[start + i]);
+                }
+                let noise = noise.map_or(T::zero(), |noise| noise[start + i]);
+                *sample = self.process(*sample, noise);
+            }
+        }
     }
 
-    pub fn process(&mut self, excitation: f64, noise: f64) -> f64 {
-        // run step twice per sample
-        self.step(excitation, noise);
-        self.step(excitation, noise);
+    /// Reshapes the tract over a block of `length` samples and prepares its reflections to glide
+    /// from their current values to the new shape.
+    fn begin_block(&mut self, length: usize) {
+        self.oral.hold_reflections();
+        self.nasal.hold_reflections();
+        self.velum.hold_reflections();
+        let recoveries = self.recoveries;
 
-        // sample output from the right-end of tract.
-        return self.oral.right[self.nasal_length - 1] + self.nasal.right[self.nasal_length - 1];
+        self.pre_block();
+        self.reshape(length as f64 / self.sample_rate);
+        self.post_block();
+
+        if self.recoveries != recoveries {
+            // the previous shape was discarded so jump straight to the new one
+            self.oral.hold_reflections();
+            self.nasal.hold_reflections();
+            self.velum.hold_reflections();
+        }
+        self.oral.begin_interpolation(length);
+        self.nasal.begin_interpolation(length);
+        self.velum.begin_interpolation(length);
+    }
+
+    /// Processes a sample of excitation, returning silence (and recovering) if the tract becomes
+    /// unstable.
+    pub fn process(&mut self, excitation: T, noise: T) -> T {
+        // sample output from the right-end of tract at each step, then filter down to the
+        // sample rate so that content above the Nyquist frequency doesn't alias
+        let mut steps = [T::zero(); MAX_OVERSAMPLING];
+        let steps = &mut steps[..self.oversampling().factor()];
+        for step in steps.iter_mut() {
+            self.step(excitation, noise);
+            *step = self.oral.right[self.oral_length - 1] + self.nasal.right[self.nasal_length - 1];
+        }
+        let output = self.decimator.process(steps);
+        if !output.is_finite() {
+            self.recover(TractError::NonFinite);
+            T::zero()
+        } else if output.abs() > T::from_f64(OVERFLOW_THRESHOLD) {
+            self.recover(TractError::Overflow {
+                value: output.as_f64(),
+            });
+            T::zero()
+        } else {
+            output
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_diameters_validates() {
+        let mut tract = Tract::new(44, 28);
+        assert!(tract.set_diameters(&[1.0; 10]).is_err());
+        assert!(tract.set_diameters(&[-1.0; 44]).is_err());
+        assert!(tract.set_diameters(&[2.0; 44]).is_ok());
+    }
+
+    #[test]
+    fn test_enable_subglottis_validates() {
+        let mut tract = Tract::new(44, 28);
+        assert!(tract.enable_subglottis(0).is_err());
+        assert!(tract.subglottis.is_none());
+        assert!(tract.enable_subglottis(20).is_ok());
+        assert!(tract.subglottis.is_some());
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_recovers_from_instability() {
+        let mut tract = Tract::new(44, 28);
+        tract.init();
+        tract.post_block();
+
+        tract.process(f64::NAN, 0.0);
+        for _ in 0..100 {
+            assert!(tract.process(f64::NAN, 0.0).is_finite());
+        }
+        assert_eq!(tract.take_error(), Some(TractError::NonFinite));
+        assert_eq!(tract.take_error(), None);
+
+        tract.reset();
+        tract.process(1e6, 0.0);
+        for _ in 0..100 {
+            assert!(tract.process(0.0, 0.0).abs() <= OVERFLOW_THRESHOLD);
+        }
+        assert!(matches!(
+            tract.take_error(),
+            Some(TractError::Overflow { .. })
+        ));
+
+        tract.oral.diameter[5] = f64::NAN;
+        tract.post_block();
+        assert!(matches!(
+            tract.take_error(),
+            Some(TractError::InvalidDiameter { index: 5, .. })
+        ));
+        assert!(tract.oral.k.iter().all(|k| k.is_finite()));
+        assert!(tract.recoveries() >= 3);
+    }
+
+    #[test]
+    fn test_oversampling() {
+        // the tract's lengths scale with the oversampling so its formants are kept
+        let tract = Tract::new(44, 28);
+        for oversampling in [Oversampling::X1, Oversampling::X4] {
+            let scaled = TractBuilder::new()
+                .oversampling(oversampling)
+                .build::<f64>()
+                .unwrap();
+            let formants = scaled.formants();
+            for expected in tract.formants().iter().take(3) {
+                assert!(formants
+                    .iter()
+                    .any(|formant| (formant.frequency / expected.frequency - 1.0).abs() < 0.05));
+            }
+        }
+
+        let mut oversampled = TractBuilder::new()
+            .oversampling(Oversampling::X4)
+            .build::<f64>()
+            .unwrap();
+
+        oversampled.init();
+        oversampled.post_block();
+        let mut buffer = [0.0; 256];
+        buffer[0] = 1.0;
+        oversampled.process_block(&mut buffer);
+        assert!(buffer.iter().all(|sample| sample.is_finite()));
+        assert!(buffer.iter().any(|&sample| sample != 0.0));
+        assert_eq!(oversampled.take_error(), None);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_process_block() {
+        let mut tract = TractBuilder::new().build::<f32>().unwrap();
+        tract.init();
+        tract.post_block();
+        let mut expected = tract.clone();
+
+        // a settled tract matches sample-by-sample processing
+        let mut buffer = [0.0; 100];
+        buffer[0] = 1.0;
+        let mut input = buffer;
+        tract.process_block(&mut buffer);
+        for (block, chunk) in input.chunks_mut(BLOCK_SIZE).enumerate() {
+            expected.reshape(chunk.len() as f64 / 44100.0);
+            expected.post_block();
+            for sample in chunk.iter_mut() {
+                *sample = expected.process(*sample, 0.0);
+            }
+            assert_eq!(buffer[block * BLOCK_SIZE..][..chunk.len()], *chunk);
+        }
+
+        // reflections glide to the new shape by the end of each block
+        tract.set_vowel(Vowel::I);
+        tract.process_block(&mut [0.0; BLOCK_SIZE]);
+        let reached = tract.oral.k.clone();
+        tract.calculate_oral_reflections();
+        for (reached, k) in reached.iter().zip(&tract.oral.k) {
+            assert!((reached - k).abs() < 1e-5);
+        }
+
+        // and so do the nasal reflections as the velum opens
+        tract.set_velum_opening(1.0);
+        let held = tract.nasal.k.clone();
+        tract.begin_block(BLOCK_SIZE);
+        assert_eq!(tract.nasal.k, held);
+        for _ in 0..BLOCK_SIZE {
+            tract.nasal.interpolate();
+        }
+        let reached = tract.nasal.k.clone();
+        tract.calculate_nasal_reflections();
+        assert_ne!(held, reached);
+        for (reached, k) in reached.iter().zip(&tract.nasal.k) {
+            assert!((reached - k).abs() < 1e-5);
+        }
     }
 }
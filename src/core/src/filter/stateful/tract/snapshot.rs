@@ -0,0 +1,273 @@
+//! Snapshots of the tract's complete state, for checkpointing, forking and reproducing renders.
+
+use super::cavity::Cavity;
+use super::consonants::Constriction;
+use super::error::{validate_diameters, TractError};
+use super::{Tract, TractBuilder, Transient};
+use crate::float::Float;
+
+/// A saved state of a [`Cavity`], in double precision.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CavitySnapshot {
+    /// Diameter of each section.
+    pub diameter: Vec<f64>,
+    /// Cross-sectional area of each section.
+    pub area: Vec<f64>,
+    /// Reflections at each junction.
+    pub k: Vec<f64>,
+    /// Left-moving components at each section.
+    pub left: Vec<f64>,
+    /// Right-moving components at each section.
+    pub right: Vec<f64>,
+}
+
+impl CavitySnapshot {
+    fn capture<T: Float>(cavity: &Cavity<T>) -> CavitySnapshot {
+        let widen = |values: &[T]| values.iter().map(|value| value.as_f64()).collect();
+        CavitySnapshot {
+            diameter: cavity.diameter.clone(),
+            area: cavity.area.clone(),
+            k: widen(&cavity.k),
+            left: widen(&cavity.left),
+            right: widen(&cavity.right),
+        }
+    }
+
+    /// Copies the snapshot into a cavity of the same length.
+    fn apply<T: Float>(&self, cavity: &mut Cavity<T>) -> Result<(), TractError> {
+        let length = cavity.diameter.len();
+        for (values, expected) in [
+            (&self.diameter, length),
+            (&self.area, length),
+            (&self.k, length + 1),
+            (&self.left, length),
+            (&self.right, length),
+        ] {
+            if values.len() != expected {
+                return Err(TractError::LengthMismatch {
+                    expected,
+                    actual: values.len(),
+                });
+            }
+        }
+        validate_diameters(&self.diameter)?;
+
+        cavity.diameter.copy_from_slice(&self.diameter);
+        cavity.area.copy_from_slice(&self.area);
+        for (values, saved) in [
+            (&mut cavity.k, &self.k),
+            (&mut cavity.left, &self.left),
+            (&mut cavity.right, &self.right),
+        ] {
+            for (value, &saved) in values.iter_mut().zip(saved) {
+                *value = T::from_f64(saved);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A saved state of a [`Tract`], from which it can be restored.
+///
+/// Snapshots are stored in double precision, so may be restored into a tract of either precision.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TractSnapshot {
+    /// The configuration of the tract.
+    pub config: TractBuilder,
+    /// The oral cavity.
+    pub oral: CavitySnapshot,
+    /// The nasal cavity.
+    pub nasal: CavitySnapshot,
+    /// The subglottal cavity (if coupled).
+    pub subglottis: Option<CavitySnapshot>,
+    /// Reflections at the velum towards the glottis, lips and nose.
+    pub velum_reflections: (f64, f64, f64),
+    /// The velum's target diameter.
+    pub velum_target_diameter: f64,
+    /// The rest diameter of the oral cavity.
+    pub oral_diameter_rest: Vec<f64>,
+    /// The target diameter of the oral cavity.
+    pub oral_diameter_target: Vec<f64>,
+    /// The index and amplitude of each alive transient.
+    pub transients: Vec<(usize, f64)>,
+    /// The index of the most recent closure within the oral cavity.
+    pub last_obstruction: Option<usize>,
+    /// The current constriction of the oral cavity.
+    pub constriction: Option<Constriction>,
+    /// The current glottal opening.
+    pub glottal_opening: f64,
+    /// The delayed samples of the output decimator.
+    pub decimator: Vec<f64>,
+}
+
+impl<T: Float> Tract<T> {
+    /// Captures the complete state of the tract.
+    pub fn snapshot(&self) -> TractSnapshot {
+        let velum = &self.velum;
+        TractSnapshot {
+            config: self.builder(),
+            oral: CavitySnapshot::capture(&self.oral),
+            nasal: CavitySnapshot::capture(&self.nasal),
+            subglottis: self
+                .subglottis
+                .as_ref()
+                .map(|subglottis| CavitySnapshot::capture(&subglottis.cavity)),
+            velum_reflections: (
+                velum.k_left.as_f64(),
+                velum.k_right.as_f64(),
+                velum.k_nose.as_f64(),
+            ),
+            velum_target_diameter: velum.target_diameter,
+            oral_diameter_rest: self.oral_diameter_rest.clone(),
+            oral_diameter_target: self.oral_diameter_target.clone(),
+            transients: self
+                .transients
+                .iter()
+                .map(|transient| (transient.index, transient.amplitude.as_f64()))
+                .collect(),
+            last_obstruction: self.last_obstruction,
+            constriction: self.constriction,
+            glottal_opening: self.glottal_opening,
+            decimator: self.decimator.state(),
+        }
+    }
+
+    /// Creates a tract from a snapshot.
+    pub fn from_snapshot(snapshot: &TractSnapshot) -> Result<Tract<T>, TractError> {
+        let mut tract = snapshot.config.build()?;
+        snapshot.oral.apply(&mut tract.oral)?;
+        snapshot.nasal.apply(&mut tract.nasal)?;
+        match (&snapshot.subglottis, &mut tract.subglottis) {
+            (Some(saved), Some(subglottis)) => saved.apply(&mut subglottis.cavity)?,
+            (None, None) => (),
+            (saved, subglottis) => {
+                return Err(TractError::LengthMismatch {
+                    expected: subglottis
+                        .as_ref()
+                        .map_or(0, |subglottis| subglottis.length),
+                    actual: saved.as_ref().map_or(0, |saved| saved.diameter.len()),
+                })
+            }
+        }
+
+        for shape in [&snapshot.oral_diameter_rest, &snapshot.oral_diameter_target] {
+            if shape.len() != tract.oral_length {
+                return Err(TractError::LengthMismatch {
+                    expected: tract.oral_length,
+                    actual: shape.len(),
+                });
+            }
+            validate_diameters(shape)?;
+        }
+        let out_of_range = snapshot
+            .transients
+            .iter()
+            .map(|&(index, _)| index)
+            .chain(snapshot.last_obstruction)
+            .find(|&index| index >= tract.oral_length);
+        if let Some(index) = out_of_range {
+            return Err(TractError::LengthMismatch {
+                expected: tract.oral_length,
+                actual: index + 1,
+            });
+        }
+
+        if !tract.decimator.set_state(&snapshot.decimator) {
+            return Err(TractError::LengthMismatch {
+                expected: tract.decimator.state().len(),
+                actual: snapshot.decimator.len(),
+            });
+        }
+
+        let (k_left, k_right, k_nose) = snapshot.velum_reflections;
+        tract.velum.k_left = T::from_f64(k_left);
+        tract.velum.k_right = T::from_f64(k_right);
+        tract.velum.k_nose = T::from_f64(k_nose);
+        tract.velum.target_diameter = snapshot.velum_target_diameter;
+        tract
+            .oral_diameter_rest
+            .copy_from_slice(&snapshot.oral_diameter_rest);
+        tract
+            .oral_diameter_target
+            .copy_from_slice(&snapshot.oral_diameter_target);
+        tract.transients = snapshot
+            .transients
+            .iter()
+            .map(|&(index, amplitude)| Transient {
+                index,
+                amplitude: T::from_f64(amplitude),
+            })
+            .collect();
+        tract.last_obstruction = snapshot.last_obstruction;
+        tract.constriction = snapshot.constriction;
+        tract.glottal_opening = snapshot.glottal_opening;
+        Ok(tract)
+    }
+
+    /// Restores the tract to a previously captured state (including its configuration).
+    ///
+    /// The tract is left unchanged if the snapshot is invalid.
+    pub fn restore(&mut self, snapshot: &TractSnapshot) -> Result<(), TractError> {
+        *self = Tract::from_snapshot(snapshot)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::vowels::Vowel;
+    use super::*;
+
+    fn render<T: Float>(tract: &mut Tract<T>, length: usize) -> Vec<f64> {
+        (0..length)
+            .map(|i| {
+                if i % 64 == 0 {
+                    tract.reshape(64.0 / 44100.0);
+                    tract.post_block();
+                }
+                tract
+                    .process(T::from_f64((i as f64 * 0.05).sin()), T::zero())
+                    .as_f64()
+            })
+            .collect()
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_snapshot_restore() {
+        let mut tract = TractBuilder::new()
+            .subglottis(Some(20))
+            .build::<f64>()
+            .unwrap();
+        tract.init();
+        tract.set_vowel(Vowel::I);
+        render(&mut tract, 500);
+
+        let snapshot = tract.snapshot();
+        let expected = render(&mut tract, 500);
+
+        let mut fork = Tract::new(30, 20);
+        fork.restore(&snapshot).unwrap();
+        assert_eq!(render(&mut fork, 500), expected);
+
+        // snapshots may be restored in single precision
+        let mut single = Tract::<f32>::from_snapshot(&snapshot).unwrap();
+        for (a, b) in render(&mut single, 500).iter().zip(&expected) {
+            assert!((a - b).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_reject_malformed_snapshot() {
+        let mut tract = Tract::new(44, 28);
+        let mut snapshot = tract.snapshot();
+        snapshot.oral.left.pop();
+        assert!(tract.restore(&snapshot).is_err());
+
+        let mut snapshot = tract.snapshot();
+        snapshot.transients.push((44, 0.1));
+        assert!(tract.restore(&snapshot).is_err());
+    }
+}
@@ -0,0 +1,98 @@
+use super::cavity::Cavity;
+use super::utils::kelly_lochbaum;
+use crate::float::Float;
+
+/// Coefficient of reflection at the lungs (a lossy, approximately open termination).
+const K_LUNG: f64 = -0.7;
+/// The diameter of the trachea.
+const TRACHEAL_DIAMETER: f64 = 1.8;
+/// The additional diameter gained as the bronchi branch towards the lungs.
+const BRONCHIAL_FLARE: f64 = 1.2;
+/// The cross-sectional area of a fully abducted glottis.
+pub const GLOTTAL_AREA_MAX: f64 = 0.2;
+
+/// The subglottal tract (trachea and main bronchi), terminated by the lungs.
+///
+/// Section `0` lies at the lungs and the final section lies directly beneath the glottis,
+/// so right-moving components travel towards the vocal tract.
+#[derive(Clone, Debug)]
+pub struct Subglottis<T: Float> {
+    /// The number of sections in the subglottal tract.
+    pub length: usize,
+    /// The subglottal cavity.
+    pub cavity: Cavity<T>,
+}
+
+impl<T: Float> Subglottis<T> {
+    /// Creates a new subglottal tract with a given number of sections.
+    pub fn new(length: usize) -> Subglottis<T> {
+        let mut cavity = Cavity::new(length);
+
+        // The trachea is roughly uniform and flares as it divides into the bronchi
+        for m in 0..length {
+            let x = 1.0 - (m as f64 / length as f64);
+            cavity.diameter[m] = TRACHEAL_DIAMETER + BRONCHIAL_FLARE * x * x * x;
+        }
+        cavity.calculate_reflections();
+
+        Subglottis { length, cavity }
+    }
+
+    /// Scatters components at the glottal junction, coupling the subglottal tract with the
+    /// first section of the oral cavity through a glottal opening of `glottal_area`.
+    ///
+    /// Returns the component transmitted up into the oral cavity, and updates the
+    /// subglottal junction with the reflected and down-going components.
+    pub fn couple(&mut self, oral_area: f64, oral_left: T, glottal_area: f64) -> (T, T) {
+        let last = self.length - 1;
+        let glottal_area = glottal_area.max(0.0);
+
+        // Reflections seen from above and below the glottis (closed glottis => total reflection)
+        let k_supra = T::from_f64(kelly_lochbaum(oral_area, glottal_area));
+        let k_sub = T::from_f64(kelly_lochbaum(self.cavity.area[last], glottal_area));
+
+        self.cavity.j_left[self.length] =
+            self.cavity.right[last] * k_sub + oral_left * (T::one() - k_supra);
+
+        (k_supra, self.cavity.right[last] * (T::one() - k_sub))
+    }
+
+    /// Simulates the propagation of sound within the subglottal tract.
+    pub fn step(&mut self, attenuation: T) {
+        let cavity = &mut self.cavity;
+        cavity.j_right[0] = cavity.left[0] * T::from_f64(K_LUNG);
+        cavity.scatter();
+        cavity.propagate(attenuation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_closed_glottis_decouples() {
+        let mut subglottis = Subglottis::<f64>::new(40);
+        let last = subglottis.length - 1;
+        subglottis.cavity.right[last] = 1.0;
+
+        let (k_supra, transmitted) = subglottis.couple(0.5, 1.0, 0.0);
+
+        assert_eq!(k_supra, 1.0);
+        assert_eq!(transmitted, 0.0);
+        assert_eq!(subglottis.cavity.j_left[subglottis.length], 1.0);
+    }
+
+    #[test]
+    fn test_open_glottis_couples() {
+        let mut subglottis = Subglottis::<f64>::new(40);
+        let last = subglottis.length - 1;
+        subglottis.cavity.right[last] = 1.0;
+
+        let (k_supra, transmitted) = subglottis.couple(0.5, 0.0, GLOTTAL_AREA_MAX);
+
+        assert!(k_supra < 1.0);
+        assert!(transmitted > 0.0);
+    }
+}
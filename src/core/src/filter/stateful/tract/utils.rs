@@ -25,3 +25,12 @@ pub fn min(a: f64, b: f64) -> f64 {
         b
     }
 }
+
+/// Returns a value moved towards a target by at most `up` (increasing) or `down` (decreasing).
+pub fn move_towards(current: f64, target: f64, up: f64, down: f64) -> f64 {
+    if current < target {
+        (current + up).min(target)
+    } else {
+        (current - down).max(target)
+    }
+}
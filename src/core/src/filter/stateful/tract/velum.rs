@@ -1,21 +1,54 @@
+use crate::float::Float;
+
 /// The nasopharyngeal junction.
 #[derive(Clone, Debug)]
-pub struct Velum {
-    pub k_right: f64,
-    pub k_left: f64,
-    pub k_nose: f64,
+pub struct Velum<T: Float> {
+    pub k_right: T,
+    pub k_left: T,
+    pub k_nose: T,
+    /// Change in (right, left, nose) reflections per interpolation step.
+    pub dk: (T, T, T),
+    /// The (right, left, nose) reflections from which the next interpolation starts.
+    pub held_k: (T, T, T),
     pub target_diameter: f64,
     pub max_diameter: f64,
 }
 
-impl Velum {
-    pub fn new(target_diameter: f64, max_diameter: f64) -> Velum {
+impl<T: Float> Velum<T> {
+    pub fn new(target_diameter: f64, max_diameter: f64) -> Velum<T> {
         Velum {
-            k_right: 0.0,
-            k_left: 0.0,
-            k_nose: 0.0,
+            k_right: T::zero(),
+            k_left: T::zero(),
+            k_nose: T::zero(),
+            dk: (T::zero(), T::zero(), T::zero()),
+            held_k: (T::zero(), T::zero(), T::zero()),
             target_diameter,
             max_diameter,
         }
     }
+
+    /// Holds the current reflections as the start of the next interpolation.
+    pub fn hold_reflections(&mut self) {
+        self.held_k = (self.k_right, self.k_left, self.k_nose);
+    }
+
+    /// Glides the reflections from their held values to their current values over `steps` calls
+    /// to [`Velum::interpolate`], starting from the held values.
+    pub fn begin_interpolation(&mut self, steps: usize) {
+        let scale = T::one() / T::from_f64(steps.max(1) as f64);
+        let previous = self.held_k;
+        self.dk = (
+            (self.k_right - previous.0) * scale,
+            (self.k_left - previous.1) * scale,
+            (self.k_nose - previous.2) * scale,
+        );
+        (self.k_right, self.k_left, self.k_nose) = previous;
+    }
+
+    /// Advances the reflections by one interpolation step.
+    pub fn interpolate(&mut self) {
+        self.k_right += self.dk.0;
+        self.k_left += self.dk.1;
+        self.k_nose += self.dk.2;
+    }
 }
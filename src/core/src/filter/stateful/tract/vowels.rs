@@ -0,0 +1,204 @@
+//! Vowel presets for the vocal tract, keyed by IPA symbol.
+//!
+//! Each vowel is described by an [`Articulation`] (tongue position, tongue height and lip
+//! opening) which can be realised as target diameters for a tract of any length.
+
+use super::write_neutral_diameters;
+use std::f64::consts::PI;
+
+/// The tongue and lip model is specified on a 44 section grid and scaled to the tract length.
+pub(crate) const GRID_LENGTH: f64 = 44.0;
+/// The grid index at which the tongue blade begins.
+const BLADE_START: f64 = 10.0;
+/// The grid index at which the tongue tip begins.
+const TIP_START: f64 = 32.0;
+/// The grid index at which the lips begin.
+const LIP_START: f64 = 39.0;
+/// The diameter of the tract at the tongue's resting height (grid units).
+const REST_DIAMETER: f64 = 1.5;
+/// The distance between the tongue's surface and its centre of rotation (grid units).
+const GRID_OFFSET: f64 = 1.7;
+/// Scales grid units into the diameters used by the tract's oral cavity.
+const DIAMETER_SCALE: f64 = 2.0;
+
+/// The position of the articulators that shape the oral cavity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Articulation {
+    /// Position of the tongue's constriction along the tract in range [0-1] (glottis to lips).
+    pub tongue_index: f64,
+    /// Height of the tongue, from 2.05 (closest to the palate) to 3.5 (lowest).
+    pub tongue_diameter: f64,
+    /// Opening of the lips, from 0 (closed) to 1.5 (spread).
+    pub lip_diameter: f64,
+}
+
+impl Articulation {
+    /// Linearly interpolates between two articulations (`t` in range [0-1]).
+    pub fn lerp(&self, other: &Articulation, t: f64) -> Articulation {
+        let mix = |a: f64, b: f64| a + (b - a) * t;
+        Articulation {
+            tongue_index: mix(self.tongue_index, other.tongue_index),
+            tongue_diameter: mix(self.tongue_diameter, other.tongue_diameter),
+            lip_diameter: mix(self.lip_diameter, other.lip_diameter),
+        }
+    }
+
+    /// Returns the diameter of each section of an oral cavity of `oral_length` sections.
+    pub fn diameters(&self, oral_length: usize) -> Vec<f64> {
+        let mut diameters = vec![0.0; oral_length];
+        self.write_diameters(&mut diameters);
+        diameters
+    }
+
+    /// Writes the diameter of each section of an oral cavity, in place (without allocating).
+    pub fn write_diameters(&self, diameters: &mut [f64]) {
+        let oral_length = diameters.len();
+        write_neutral_diameters(diameters);
+        let scale = GRID_LENGTH / oral_length as f64;
+        let tongue_index = self.tongue_index * GRID_LENGTH;
+        let tongue_diameter = 2.0 + (self.tongue_diameter - 2.0) / 1.5;
+
+        for (m, diameter) in diameters.iter_mut().enumerate() {
+            let i = m as f64 * scale;
+            if i >= LIP_START {
+                *diameter = self.lip_diameter * DIAMETER_SCALE;
+            } else if i >= BLADE_START {
+                let t = 1.1 * PI * (tongue_index - i) / (TIP_START - BLADE_START);
+                let curve = (REST_DIAMETER - tongue_diameter + GRID_OFFSET) * t.cos();
+                *diameter = (REST_DIAMETER - curve) * DIAMETER_SCALE;
+            }
+        }
+    }
+}
+
+/// A vowel, named after its IPA symbol.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Vowel {
+    /// Close front unrounded vowel (i).
+    I,
+    /// Close front rounded vowel (y).
+    Y,
+    /// Near-close near-front unrounded vowel (ɪ).
+    SmallCapitalI,
+    /// Close-mid front unrounded vowel (e).
+    E,
+    /// Close-mid front rounded vowel (ø).
+    OSlash,
+    /// Open-mid front unrounded vowel (ɛ).
+    Epsilon,
+    /// Near-open front unrounded vowel (æ).
+    Ash,
+    /// Open front unrounded vowel (a).
+    A,
+    /// Open back unrounded vowel (ɑ).
+    Alpha,
+    /// Open-mid back rounded vowel (ɔ).
+    OpenO,
+    /// Close-mid back rounded vowel (o).
+    O,
+    /// Close back rounded vowel (u).
+    U,
+    /// Near-close near-back rounded vowel (ʊ).
+    Upsilon,
+    /// Open-mid back unrounded vowel (ʌ).
+    TurnedV,
+    /// Mid central vowel (ə).
+    Schwa,
+}
+
+/// Every vowel with its IPA symbol and articulation (grid index, tongue diameter, lip diameter).
+const VOWELS: [(Vowel, &str, f64, f64, f64); 15] = [
+    (Vowel::I, "i", 28.0, 2.10, 1.30),
+    (Vowel::Y, "y", 28.0, 2.10, 0.45),
+    (Vowel::SmallCapitalI, "ɪ", 26.5, 2.40, 1.20),
+    (Vowel::E, "e", 27.0, 2.50, 1.20),
+    (Vowel::OSlash, "ø", 27.0, 2.50, 0.50),
+    (Vowel::Epsilon, "ɛ", 25.0, 2.85, 1.30),
+    (Vowel::Ash, "æ", 22.0, 3.20, 1.40),
+    (Vowel::A, "a", 16.5, 3.00, 1.40),
+    (Vowel::Alpha, "ɑ", 13.5, 2.60, 1.30),
+    (Vowel::OpenO, "ɔ", 14.5, 2.70, 0.80),
+    (Vowel::O, "o", 18.0, 2.45, 0.55),
+    (Vowel::U, "u", 21.5, 2.15, 0.35),
+    (Vowel::Upsilon, "ʊ", 20.5, 2.50, 0.65),
+    (Vowel::TurnedV, "ʌ", 17.0, 3.00, 1.10),
+    (Vowel::Schwa, "ə", 19.0, 3.50, 1.00),
+];
+
+impl Vowel {
+    /// Returns every vowel in the library.
+    pub fn all() -> impl Iterator<Item = Vowel> {
+        VOWELS.iter().map(|entry| entry.0)
+    }
+
+    /// Returns the vowel for a given IPA symbol.
+    pub fn from_ipa(symbol: &str) -> Option<Vowel> {
+        VOWELS
+            .iter()
+            .find(|entry| entry.1 == symbol)
+            .map(|entry| entry.0)
+    }
+
+    /// Returns the vowel's IPA symbol.
+    pub fn ipa(&self) -> &'static str {
+        self.entry().1
+    }
+
+    /// Returns the position of the articulators for this vowel.
+    pub fn articulation(&self) -> Articulation {
+        let &(_, _, tongue_index, tongue_diameter, lip_diameter) = self.entry();
+        Articulation {
+            tongue_index: tongue_index / GRID_LENGTH,
+            tongue_diameter,
+            lip_diameter,
+        }
+    }
+
+    /// Returns the target diameters of this vowel for an oral cavity of `oral_length` sections.
+    pub fn diameters(&self, oral_length: usize) -> Vec<f64> {
+        self.articulation().diameters(oral_length)
+    }
+
+    /// Interpolates between the articulations of two vowels (`t` in range [0-1]).
+    pub fn interpolate(&self, other: &Vowel, t: f64) -> Articulation {
+        self.articulation().lerp(&other.articulation(), t)
+    }
+
+    fn entry(&self) -> &'static (Vowel, &'static str, f64, f64, f64) {
+        VOWELS.iter().find(|entry| entry.0 == *self).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ipa_round_trip() {
+        for vowel in Vowel::all() {
+            assert_eq!(Vowel::from_ipa(vowel.ipa()), Some(vowel));
+        }
+        assert_eq!(Vowel::from_ipa("a"), Some(Vowel::A));
+        assert_eq!(Vowel::from_ipa("x"), None);
+    }
+
+    #[test]
+    fn test_diameters_scale_with_tract_length() {
+        for &oral_length in &[30, 44, 60] {
+            let diameters = Vowel::A.diameters(oral_length);
+            assert_eq!(diameters.len(), oral_length);
+            assert!(diameters.iter().all(|&d| d > 0.0));
+        }
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let i = Vowel::I.articulation();
+        let u = Vowel::U.articulation();
+        assert_eq!(Vowel::I.interpolate(&Vowel::U, 0.0), i);
+        assert_eq!(Vowel::I.interpolate(&Vowel::U, 1.0), u);
+
+        let halfway = Vowel::I.interpolate(&Vowel::U, 0.5);
+        assert!(halfway.lip_diameter < i.lip_diameter && halfway.lip_diameter > u.lip_diameter);
+    }
+}
@@ -0,0 +1,49 @@
+//! Floating point precision.
+//!
+//! Signal processing components are generic over [`Float`] so that real-time hosts can run in
+//! single precision while offline renders keep double precision. Audio-rate signals and filter
+//! state use the chosen precision, whereas time, pitch and articulation parameters stay `f64`
+//! (time in particular loses precision quickly in `f32`).
+
+use std::fmt::Debug;
+use std::ops::{AddAssign, DivAssign, MulAssign, SubAssign};
+
+/// A floating point sample type (implemented for `f32` and `f64`).
+pub trait Float:
+    num_traits::Float
+    + num_traits::FloatConst
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + DivAssign
+    + Default
+    + Debug
+    + Send
+    + Sync
+    + 'static
+{
+    /// Converts from `f64`, rounding if necessary.
+    fn from_f64(value: f64) -> Self;
+    /// Converts to `f64`.
+    fn as_f64(self) -> f64;
+}
+
+impl Float for f32 {
+    fn from_f64(value: f64) -> f32 {
+        value as f32
+    }
+
+    fn as_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Float for f64 {
+    fn from_f64(value: f64) -> f64 {
+        value
+    }
+
+    fn as_f64(self) -> f64 {
+        self
+    }
+}
@@ -1,2 +1,8 @@
 pub mod filter;
+pub mod float;
 pub mod source;
+pub mod speech;
+pub mod utils;
+pub mod voice;
+
+pub use float::Float;
@@ -1,44 +1,140 @@
-use super::{gaussian_noise, liljencrants_fant};
+use super::{liljencrants_fant_timing, LiljencrantsFant};
+use crate::float::Float;
+use crate::utils::noise::WhiteNoise;
 use std::f64::consts::PI;
+use std::io::{Error, ErrorKind, Result};
 const PI2: f64 = PI * 2.0;
+/// The glottal opening that persists through the closed phase of a fully lax glottis.
+const GLOTTAL_LEAKAGE: f64 = 0.25;
+/// The number of samples in the aspiration buffer.
+const ASPIRATION_LENGTH: usize = 128;
+/// The amplitude of fully open aspiration noise relative to the glottal excitation.
+const ASPIRATION_GAIN: f64 = 0.3;
+/// The default sample rate of the internal clock, in Hz.
+const SAMPLE_RATE: f64 = 44100.0;
 
+#[derive(Clone, Debug)]
 struct Vibrato {
     frequency: f64,
     amplitude: f64,
 }
 
-pub struct Glottis {
+/// A glottal source, producing excitation of precision `T`.
+#[derive(Clone, Debug)]
+pub struct Glottis<T: Float = f64> {
     pub frequency: f64,
     pub tenseness: f64,
     vibrato: Vibrato,
     intensity: f64,
+    /// The amplitude of turbulence noise through the glottis in range [0-1].
+    aspiration: f64,
     loudness: f64,
-    wave_function: Box<dyn Fn(f64) -> f64 + Send>,
-    aspiration_buffer: [f64; 128],
+    wave: LiljencrantsFant<T>,
+    aspiration_buffer: [T; ASPIRATION_LENGTH],
     aspiration_index: usize,
+    /// Instants of maximum glottal flow and closure, normalised to a period of 1.
+    timing: (f64, f64),
+    /// The most recent glottal opening in range [0-1].
+    opening: f64,
+    /// The sample rate of the internal clock used by [`Glottis::process_block`].
+    sample_rate: f64,
+    /// The time of the internal clock in seconds.
+    time: f64,
+    /// The phase of the glottal cycle as of the most recent block sample, in range [0-1).
+    phase: f64,
+    /// The frequency, tenseness and intensity reached by the end of the most recent block.
+    block_frequency: f64,
+    block_tenseness: f64,
+    block_intensity: f64,
+    block_aspiration: f64,
+    /// The source of turbulence noise through the glottis.
+    turbulence: WhiteNoise,
 }
 
-impl Glottis {
-    pub fn new() -> Glottis {
+impl<T: Float> Glottis<T> {
+    pub fn new() -> Glottis<T> {
         let vibrato: Vibrato = Vibrato {
             frequency: 6.0,
             amplitude: 0.1,
         };
 
+        let mut turbulence = WhiteNoise::new(0);
         Glottis {
             frequency: 440.0, // A4
             vibrato,
             intensity: 0.5,
+            aspiration: 0.0,
             tenseness: 0.5,
             loudness: 0.1,
-            wave_function: Box::new(liljencrants_fant(0.5)),
-            aspiration_buffer: create_aspiration_buffer(),
+            wave: LiljencrantsFant::new(0.5),
+            aspiration_buffer: create_aspiration_buffer(&mut turbulence),
             aspiration_index: 0,
+            timing: glottal_timing(0.5),
+            opening: 0.0,
+            sample_rate: SAMPLE_RATE,
+            time: 0.0,
+            phase: 0.0,
+            block_frequency: 440.0,
+            block_tenseness: 0.5,
+            block_intensity: 0.5,
+            block_aspiration: 0.0,
+            turbulence,
         }
     }
 
+    /// Sets the sample rate of the internal clock used by [`Glottis::process_block`].
+    pub fn set_sample_rate(&mut self, sample_rate: f64) {
+        self.sample_rate = sample_rate;
+    }
+
+    /// Sets the amplitude of the glottal excitation in range [0-1].
+    pub fn set_intensity(&mut self, intensity: f64) {
+        self.intensity = intensity.clamp(0.0, 1.0);
+    }
+
+    /// Returns the amplitude of the glottal excitation in range [0-1].
+    pub fn intensity(&self) -> f64 {
+        self.intensity
+    }
+
+    /// Sets the amplitude of turbulence noise through the glottis in range [0-1], heard as
+    /// aspiration (/h/) independently of the excitation's intensity.
+    pub fn set_aspiration(&mut self, aspiration: f64) {
+        self.aspiration = aspiration.clamp(0.0, 1.0);
+    }
+
+    /// Returns the amplitude of turbulence noise through the glottis in range [0-1].
+    pub fn aspiration(&self) -> f64 {
+        self.aspiration
+    }
+
+    /// Seeds the aspiration and turbulence noise, so glottises with the same seed produce the
+    /// same output.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.turbulence = WhiteNoise::new(seed);
+        self.aspiration_buffer = create_aspiration_buffer(&mut self.turbulence);
+        self.aspiration_index = 0;
+    }
+
+    /// Sets the gain applied to the glottal excitation.
+    pub fn set_loudness(&mut self, loudness: f64) {
+        self.loudness = loudness;
+    }
+
+    /// Sets the rate (in Hz) and depth (in Hz) of the vibrato.
+    pub fn set_vibrato(&mut self, frequency: f64, amplitude: f64) {
+        self.vibrato.frequency = frequency;
+        self.vibrato.amplitude = amplitude;
+    }
+
+    /// Returns the time of the internal clock in seconds.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
     pub fn pre_block(&mut self) {
-        self.wave_function = Box::new(liljencrants_fant(self.tenseness));
+        self.wave = LiljencrantsFant::new(self.tenseness);
+        self.timing = glottal_timing(self.tenseness);
         // let simplex1 = noise::simplex(1.4);
         // let simplex2 = noise::simplex(4.2);
     }
@@ -46,30 +142,245 @@ impl Glottis {
     // pub fn post_block() {}
 
     /// Generates glottal excitation at a given time.
-    pub fn tick(&mut self, time: f64) -> f64 {
+    pub fn tick(&mut self, time: f64) -> T {
+        let f0 = self.frequency + self.vibrato(time);
+        let t = (time * f0) % 1.0;
+        self.sample(t, self.tenseness, self.intensity, self.aspiration)
+    }
+
+    /// Fills a block with glottal excitation, advancing the internal clock.
+    ///
+    /// Frequency, tenseness, intensity and aspiration glide sample by sample from their values at
+    /// the end of the previous block, so that parameter changes between blocks do not click. The
+    /// waveform is rebuilt from the gliding tenseness at the start of each glottal cycle. The
+    /// phase of the glottal cycle is accumulated rather than derived from the clock, so frequency
+    /// changes stay continuous.
+    pub fn process_block(&mut self, output: &mut [T]) {
+        self.render_block(output, None);
+    }
+
+    /// Fills a block with glottal excitation as [`Glottis::process_block`], also writing the
+    /// glottal opening at each sample to couple a
+    /// [`Tract`](crate::filter::stateful::tract::Tract) to the subglottal system.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `opening` is shorter than `output`.
+    pub fn process_block_with_opening(&mut self, output: &mut [T], opening: &mut [f64]) {
+        assert!(opening.len() >= output.len(), "opening buffer is too short");
+        self.render_block(output, Some(opening));
+    }
+
+    fn render_block(&mut self, output: &mut [T], mut opening: Option<&mut [f64]>) {
+        let dt = 1.0 / self.sample_rate;
+        let length = output.len() as f64;
+        let (frequency, tenseness, intensity) = (self.frequency, self.tenseness, self.intensity);
+        let aspiration = self.aspiration;
+        for (i, sample) in output.iter_mut().enumerate() {
+            let x = (i + 1) as f64 / length;
+            let f0 = lerp(self.block_frequency, frequency, x) + self.vibrato(self.time);
+            let phase = (self.phase + f0 * dt).rem_euclid(1.0);
+            let cycle_tenseness = lerp(self.block_tenseness, tenseness, x);
+            if phase < self.phase {
+                self.wave = LiljencrantsFant::new(cycle_tenseness);
+                self.timing = glottal_timing(cycle_tenseness);
+            }
+            self.phase = phase;
+            *sample = self.sample(
+                self.phase,
+                cycle_tenseness,
+                lerp(self.block_intensity, intensity, x),
+                lerp(self.block_aspiration, aspiration, x),
+            );
+            if let Some(opening) = opening.as_deref_mut() {
+                opening[i] = self.opening;
+            }
+            self.time += dt;
+        }
+        self.block_frequency = frequency;
+        self.block_tenseness = tenseness;
+        self.block_intensity = intensity;
+        self.block_aspiration = aspiration;
+    }
+
+    /// Returns the vibrato offset from the fundamental frequency at a given time.
+    fn vibrato(&self, time: f64) -> f64 {
         // TODO: add noise back in
         // let s1 = simplex1[n] as f64;
         // let s2 = simplex2[n] as f64;
-        // let s1 = 1.0;
-        let s2 = 0.0;
-
-        // vibrato
-        let vibrato = (self.vibrato.frequency * PI2 * time).sin() * self.vibrato.amplitude;
         // vibrato += (s1 * self.vibrato.amplitude / 2.0) + (s2 * self.vibrato.amplitude / 3.0);
+        (self.vibrato.frequency * PI2 * time).sin() * self.vibrato.amplitude
+    }
+
+    /// Generates glottal excitation at phase t of the glottal cycle.
+    fn sample(&mut self, t: f64, tenseness: f64, intensity: f64, breath: f64) -> T {
+        let s2 = 0.0;
 
         // excitation
-        let f0 = self.frequency + vibrato;
-        let t = (time * f0) % 1.0;
-        let excitation = (self.wave_function)(t);
+        let excitation = self.wave.evaluate(T::from_f64(t));
+        self.opening = self.glottal_opening(t);
 
         // aspiration (gaussian buffer = aspiration)
         let aspiration = self.aspiration_buffer[self.aspiration_index];
         let modulation = hanning_modulation(t, 0.15, 0.8);
-        let noise_residual = aspiration * (1.0 + s2 * 0.25) * modulation * self.tenseness.sqrt();
-        self.aspiration_index = (self.aspiration_index + 1) % 128; // incremement & wrap within 128
+        let noise_residual =
+            aspiration * T::from_f64((1.0 + s2 * 0.25) * modulation * tenseness.sqrt());
+        self.aspiration_index = (self.aspiration_index + 1) % ASPIRATION_LENGTH; // incremement & wrap
+
+        // turbulence through the open glottis
+        let turbulence = if breath > 0.0 {
+            let noise = self.turbulence.sample();
+            T::from_f64(noise * breath * self.loudness * ASPIRATION_GAIN)
+        } else {
+            T::zero()
+        };
 
-        return (excitation) * self.intensity * self.loudness + noise_residual;
+        excitation * T::from_f64(intensity * self.loudness) + noise_residual + turbulence
     }
+
+    /// Captures the glottis' parameters and noise state.
+    ///
+    /// The phase of the glottal cycle is derived from the time passed to [`Glottis::tick`], so
+    /// restoring a snapshot and resuming at the same time reproduces the same output. Blocks
+    /// resume from the captured internal clock and phase.
+    pub fn snapshot(&self) -> GlottisSnapshot {
+        GlottisSnapshot {
+            frequency: self.frequency,
+            tenseness: self.tenseness,
+            vibrato_frequency: self.vibrato.frequency,
+            vibrato_amplitude: self.vibrato.amplitude,
+            intensity: self.intensity,
+            aspiration: self.aspiration,
+            loudness: self.loudness,
+            wave_tenseness: self.wave.tenseness,
+            aspiration_buffer: self.aspiration_buffer.iter().map(|x| x.as_f64()).collect(),
+            aspiration_index: self.aspiration_index,
+            opening: self.opening,
+            sample_rate: self.sample_rate,
+            time: self.time,
+            phase: self.phase,
+            block_frequency: self.block_frequency,
+            block_tenseness: self.block_tenseness,
+            block_intensity: self.block_intensity,
+            block_aspiration: self.block_aspiration,
+            turbulence: self.turbulence.state(),
+        }
+    }
+
+    /// Restores the glottis to a previously captured state.
+    pub fn restore(&mut self, snapshot: &GlottisSnapshot) -> Result<()> {
+        if snapshot.aspiration_buffer.len() != ASPIRATION_LENGTH
+            || snapshot.aspiration_index >= ASPIRATION_LENGTH
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "snapshot has a malformed aspiration buffer",
+            ));
+        }
+        if !self.turbulence.set_state(snapshot.turbulence) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "snapshot has an invalid turbulence state",
+            ));
+        }
+
+        self.frequency = snapshot.frequency;
+        self.tenseness = snapshot.tenseness;
+        self.vibrato.frequency = snapshot.vibrato_frequency;
+        self.vibrato.amplitude = snapshot.vibrato_amplitude;
+        self.intensity = snapshot.intensity;
+        self.aspiration = snapshot.aspiration;
+        self.loudness = snapshot.loudness;
+        self.wave = LiljencrantsFant::new(snapshot.wave_tenseness);
+        self.timing = glottal_timing(snapshot.wave_tenseness);
+        for (sample, &saved) in self
+            .aspiration_buffer
+            .iter_mut()
+            .zip(&snapshot.aspiration_buffer)
+        {
+            *sample = T::from_f64(saved);
+        }
+        self.aspiration_index = snapshot.aspiration_index;
+        self.opening = snapshot.opening;
+        self.sample_rate = snapshot.sample_rate;
+        self.time = snapshot.time;
+        self.phase = snapshot.phase;
+        self.block_frequency = snapshot.block_frequency;
+        self.block_tenseness = snapshot.block_tenseness;
+        self.block_intensity = snapshot.block_intensity;
+        self.block_aspiration = snapshot.block_aspiration;
+        Ok(())
+    }
+
+    /// Returns the glottal opening in range [0-1] as of the most recent tick.
+    pub fn opening(&self) -> f64 {
+        self.opening
+    }
+
+    /// Returns the glottal opening at phase t, rising until maximum flow and closing by closure.
+    fn glottal_opening(&self, t: f64) -> f64 {
+        let (tp, te) = self.timing;
+        let shape = if t < tp {
+            0.5 * (1.0 - (PI * t / tp).cos())
+        } else if t < te {
+            0.5 * (1.0 + (PI * (t - tp) / (te - tp)).cos())
+        } else {
+            0.0
+        };
+
+        // lax phonation never fully adducts the vocal folds
+        let leakage = GLOTTAL_LEAKAGE * (1.0 - self.tenseness);
+        leakage + (1.0 - leakage) * shape
+    }
+}
+
+/// A saved state of a [`Glottis`], from which it can be restored.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GlottisSnapshot {
+    pub frequency: f64,
+    pub tenseness: f64,
+    pub vibrato_frequency: f64,
+    pub vibrato_amplitude: f64,
+    pub intensity: f64,
+    /// The amplitude of turbulence noise through the glottis.
+    pub aspiration: f64,
+    pub loudness: f64,
+    /// The tenseness of the glottal waveform as of the most recent block.
+    pub wave_tenseness: f64,
+    /// The aspiration noise buffer.
+    pub aspiration_buffer: Vec<f64>,
+    /// The position within the aspiration noise buffer.
+    pub aspiration_index: usize,
+    /// The glottal opening as of the most recent tick.
+    pub opening: f64,
+    /// The sample rate of the internal clock.
+    pub sample_rate: f64,
+    /// The time of the internal clock in seconds.
+    pub time: f64,
+    /// The phase of the glottal cycle as of the most recent block sample.
+    pub phase: f64,
+    /// The frequency reached by the end of the most recent block.
+    pub block_frequency: f64,
+    /// The tenseness reached by the end of the most recent block.
+    pub block_tenseness: f64,
+    /// The intensity reached by the end of the most recent block.
+    pub block_intensity: f64,
+    /// The aspiration reached by the end of the most recent block.
+    pub block_aspiration: f64,
+    /// The state of the turbulence noise generator.
+    pub turbulence: u64,
+}
+
+/// Returns the instants of maximum glottal flow and closure for a given tenseness.
+fn glottal_timing(tenseness: f64) -> (f64, f64) {
+    let (_, tp, te) = liljencrants_fant_timing(tenseness);
+    (tp, te)
+}
+
+/// Linearly interpolates between a and b by x in range [0-1].
+fn lerp(a: f64, b: f64, x: f64) -> f64 {
+    a + (b - a) * x
 }
 
 /// Returns a hanning-window amplitude modulation value at point t for a given frequency.
@@ -77,8 +388,126 @@ fn hanning_modulation(t: f64, floor: f64, amplitude: f64) -> f64 {
     floor + amplitude * ((1.0 - (PI2 * t).cos()) / 2.0)
 }
 
-/// Returns an array of 128 white noise samples.
-fn create_aspiration_buffer() -> [f64; 128] {
-    let noise = gaussian_noise();
-    return [noise(0.0); 128];
+/// Returns an array of 128 gaussian noise samples drawn from `noise`.
+fn create_aspiration_buffer<T: Float>(noise: &mut WhiteNoise) -> [T; ASPIRATION_LENGTH] {
+    std::array::from_fn(|_| T::from_f64((0..16).map(|_| noise.sample()).sum::<f64>() / 8.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_snapshot_restore() {
+        let mut glottis = Glottis::new();
+        glottis.tenseness = 0.7;
+        glottis.pre_block();
+        let snapshot = glottis.snapshot();
+        let expected: Vec<f64> = (0..256).map(|i| glottis.tick(i as f64 / 44100.0)).collect();
+
+        let mut restored = Glottis::new();
+        restored.restore(&snapshot).unwrap();
+        let actual: Vec<f64> = (0..256)
+            .map(|i| restored.tick(i as f64 / 44100.0))
+            .collect();
+        assert_eq!(actual, expected);
+
+        // snapshots are portable between precisions
+        let mut single = Glottis::<f32>::new();
+        single.restore(&snapshot).unwrap();
+        for (i, expected) in expected.iter().enumerate() {
+            let actual = single.tick(i as f64 / 44100.0);
+            assert!((actual as f64 - expected).abs() < 1e-4);
+        }
+
+        let mut malformed = snapshot.clone();
+        malformed.aspiration_buffer.pop();
+        assert!(restored.restore(&malformed).is_err());
+        let mut malformed = snapshot;
+        malformed.turbulence = 0;
+        assert!(restored.restore(&malformed).is_err());
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_process_block() {
+        let mut glottis = Glottis::<f32>::new();
+        glottis.frequency = 220.0;
+        let mut first = [0.0; 64];
+        glottis.process_block(&mut first);
+        assert!((glottis.time() - 64.0 / 44100.0).abs() < 1e-12);
+        assert!(first.iter().all(|x| x.is_finite()));
+
+        // resuming from a snapshot continues the clock and phase
+        let snapshot = glottis.snapshot();
+        let mut expected = [0.0; 64];
+        let mut opening = [0.0; 64];
+        glottis.frequency = 330.0;
+        glottis.process_block_with_opening(&mut expected, &mut opening);
+        assert!(opening.iter().all(|x| (0.0..=1.0).contains(x)));
+
+        let mut restored = Glottis::<f32>::new();
+        restored.restore(&snapshot).unwrap();
+        restored.frequency = 330.0;
+        let mut actual = [0.0; 64];
+        restored.process_block(&mut actual);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parameters_glide() {
+        // a block glides as if each of its samples were rendered with the values in between
+        let mut glottis = Glottis::<f64>::new();
+        glottis.set_intensity(0.2);
+        glottis.process_block(&mut [0.0; 64]);
+        let mut stepped = glottis.clone();
+        glottis.frequency = 330.0;
+        glottis.set_intensity(0.8);
+        glottis.tenseness = 0.9;
+        let mut glided = [0.0; 1024];
+        glottis.process_block(&mut glided);
+
+        for (i, expected) in glided.iter().enumerate() {
+            let x = (i + 1) as f64 / glided.len() as f64;
+            stepped.frequency = lerp(440.0, 330.0, x);
+            stepped.set_intensity(lerp(0.2, 0.8, x));
+            stepped.tenseness = lerp(0.5, 0.9, x);
+            let mut actual = [0.0];
+            stepped.process_block(&mut actual);
+            assert!((actual[0] - expected).abs() < 1e-9);
+        }
+        assert!(glottis.wave.tenseness > 0.8);
+        assert!((stepped.wave.tenseness - glottis.wave.tenseness).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_aspiration() {
+        let mut glottis = Glottis::<f64>::new();
+        glottis.set_intensity(0.0);
+        let mut breathy = glottis.clone();
+        breathy.set_aspiration(1.0);
+        assert!((breathy.aspiration() - 1.0).abs() < 1e-12);
+
+        let mut silent = [0.0; 256];
+        let mut aspirated = [0.0; 256];
+        glottis.process_block(&mut silent);
+        breathy.process_block(&mut aspirated);
+        let difference: f64 = silent
+            .iter()
+            .zip(&aspirated)
+            .map(|(a, b)| (a - b).abs())
+            .sum();
+        assert!(difference > 1e-3);
+        assert!(aspirated.iter().all(|x| x.is_finite()));
+
+        // turbulence resumes from a snapshot
+        let snapshot = breathy.snapshot();
+        let mut restored = Glottis::<f64>::new();
+        restored.restore(&snapshot).unwrap();
+        breathy.process_block(&mut aspirated);
+        let mut resumed = [0.0; 256];
+        restored.process_block(&mut resumed);
+        assert_eq!(resumed, aspirated);
+    }
 }
@@ -0,0 +1,130 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f64::consts::PI;
+
+/// The shape of a low frequency oscillator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Waveform {
+    Sine,
+    Triangle,
+    /// A new random value each cycle, eased into from the previous one.
+    RandomSmooth,
+    /// A new random value each cycle, held until the next.
+    SampleAndHold,
+}
+
+/// A low frequency oscillator, producing control values in range [-1, 1].
+///
+/// Random waveforms are seeded, so an oscillator always produces the same sequence.
+#[derive(Clone, Debug)]
+pub struct Lfo {
+    pub waveform: Waveform,
+    /// The frequency of the oscillator in Hz.
+    pub frequency: f64,
+    sample_rate: f64,
+    /// The phase of the oscillator in range [0-1).
+    phase: f64,
+    rng: StdRng,
+    /// The random values at the start and end of the current cycle.
+    random: (f64, f64),
+    value: f64,
+}
+
+impl Lfo {
+    pub fn new(waveform: Waveform, frequency: f64, sample_rate: f64) -> Lfo {
+        Lfo::with_seed(waveform, frequency, sample_rate, 0)
+    }
+
+    /// Creates an oscillator whose random waveforms follow a given seed.
+    pub fn with_seed(waveform: Waveform, frequency: f64, sample_rate: f64, seed: u64) -> Lfo {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let random = (rng.gen_range(-1.0..=1.0), rng.gen_range(-1.0..=1.0));
+        let mut lfo = Lfo {
+            waveform,
+            frequency,
+            sample_rate,
+            phase: 0.0,
+            rng,
+            random,
+            value: 0.0,
+        };
+        lfo.value = lfo.evaluate();
+        lfo
+    }
+
+    /// Restarts the oscillator's cycle.
+    pub fn reset(&mut self) {
+        self.phase = 0.0;
+        self.value = self.evaluate();
+    }
+
+    /// Returns the current value of the oscillator.
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    /// Advances the oscillator by a sample, returning its new value.
+    pub fn tick(&mut self) -> f64 {
+        self.advance(1.0 / self.sample_rate)
+    }
+
+    /// Advances the oscillator by `delta_time` seconds, returning its new value.
+    pub fn advance(&mut self, delta_time: f64) -> f64 {
+        self.phase += self.frequency * delta_time;
+        if self.phase >= 1.0 {
+            // draw a value for each cycle completed (only the last two are ever used)
+            let cycles = (self.phase.floor() as usize).min(2);
+            for _ in 0..cycles {
+                self.random = (self.random.1, self.rng.gen_range(-1.0..=1.0));
+            }
+            self.phase = self.phase.fract();
+        }
+        self.value = self.evaluate();
+        self.value
+    }
+
+    /// Returns the value of the waveform at the current phase.
+    fn evaluate(&self) -> f64 {
+        let t = self.phase;
+        match self.waveform {
+            Waveform::Sine => (2.0 * PI * t).sin(),
+            Waveform::Triangle => 1.0 - 4.0 * (t - 0.25 - (t - 0.25).round()).abs(),
+            Waveform::RandomSmooth => {
+                let (from, to) = self.random;
+                from + (to - from) * 0.5 * (1.0 - (PI * t).cos())
+            }
+            Waveform::SampleAndHold => self.random.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_waveforms() {
+        let mut triangle = Lfo::new(Waveform::Triangle, 1.0, 4.0);
+        let values: Vec<f64> = (0..4).map(|_| triangle.tick()).collect();
+        assert_eq!(values, vec![1.0, 0.0, -1.0, 0.0]);
+
+        for waveform in [Waveform::Sine, Waveform::RandomSmooth] {
+            let mut lfo = Lfo::new(waveform, 3.0, 1000.0);
+            let mut previous = lfo.value();
+            for _ in 0..2000 {
+                let value = lfo.tick();
+                assert!((-1.0..=1.0).contains(&value));
+                assert!((value - previous).abs() < 0.1);
+                previous = value;
+            }
+        }
+
+        // sample and hold changes only at the start of each cycle
+        let mut held = Lfo::with_seed(Waveform::SampleAndHold, 1.0, 8.0, 3);
+        let first: Vec<f64> = (0..7).map(|_| held.tick()).collect();
+        assert!(first.iter().all(|&value| value == first[0]));
+        assert_ne!(held.tick(), first[0]);
+    }
+}
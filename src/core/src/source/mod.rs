@@ -9,53 +9,55 @@
 //! Amplitude is in the range [-1, 1] and will be quantized or scaled to target bit depth
 //!
 //! See the short! source for each generator for exact details on what they do.
+//!
+//! Time and parameters stay `f64`, whereas generators may produce `f32` or `f64` samples (see
+//! [`Float`]).
 
 use std::collections::VecDeque;
 use std::f64::consts::PI;
 
 use crate::filter::stateless::envelope;
+use crate::float::Float;
 
 mod glottis;
-pub use glottis::Glottis;
+mod lfo;
+pub use glottis::{Glottis, GlottisSnapshot};
+pub use lfo::{Lfo, Waveform};
 
-pub fn sine_wave(frequency: f64) -> impl Fn(f64) -> f64 {
-    move |t| (t * frequency * 2.0 * PI).sin()
+pub fn sine_wave<T: Float>(frequency: f64) -> impl Fn(f64) -> T {
+    move |t| T::from_f64((t * frequency * 2.0 * PI).sin())
 }
 
-pub fn square_wave(frequency: f64) -> impl Fn(f64) -> f64 {
+pub fn square_wave<T: Float>(frequency: f64) -> impl Fn(f64) -> T {
     move |t| {
-        let sin_wave = sine_wave(frequency);
+        let sin_wave = sine_wave::<f64>(frequency);
         if sin_wave(t).is_sign_positive() {
-            1.0
+            T::one()
         } else {
-            -1.0
+            -T::one()
         }
     }
 }
 
-pub fn sawtooth_wave(frequency: f64) -> impl Fn(f64) -> f64 {
+pub fn sawtooth_wave<T: Float>(frequency: f64) -> impl Fn(f64) -> T {
     move |t| {
         let t_factor = t * frequency;
-        t_factor - t_factor.floor() - 0.5
+        T::from_f64(t_factor - t_factor.floor() - 0.5)
     }
 }
 
-pub fn triangle_wave(frequency: f64) -> impl Fn(f64) -> f64 {
+pub fn triangle_wave<T: Float>(frequency: f64) -> impl Fn(f64) -> T {
     move |t| {
-        let sawtooth_wave = sawtooth_wave(frequency);
-        (sawtooth_wave(t).abs() - 0.25) * 4.0
+        let sawtooth_wave = sawtooth_wave::<f64>(frequency);
+        T::from_f64((sawtooth_wave(t).abs() - 0.25) * 4.0)
     }
 }
 
-pub fn tangent_wave(frequency: f64) -> impl Fn(f64) -> f64 {
-    move |t| {
-        (((t * frequency * PI) - 0.5).tan() / 4.0)
-            .max(-1.0)
-            .min(1.0)
-    }
+pub fn tangent_wave<T: Float>(frequency: f64) -> impl Fn(f64) -> T {
+    move |t| T::from_f64((((t * frequency * PI) - 0.5).tan() / 4.0).clamp(-1.0, 1.0))
 }
 
-pub fn bell(frequency: f64, attack: f64, decay: f64) -> impl Fn(f64) -> f64 {
+pub fn bell<T: Float>(frequency: f64, attack: f64, decay: f64) -> impl Fn(f64) -> T {
     move |t| {
         // TODO: lazy-static this table
         // Frequency, amplitude, decay
@@ -71,16 +73,17 @@ pub fn bell(frequency: f64, attack: f64, decay: f64) -> impl Fn(f64) -> f64 {
             (4.07, 0.003_906_25, 19.6),
         ];
 
-        harmonics_table.iter().fold(0.0, |acc, h| {
-            acc + sine_wave(frequency * h.0)(t) * h.1 * envelope(t, attack, decay * h.2)
-        }) / 2.0
+        let sample = harmonics_table.iter().fold(0.0, |acc, h| {
+            acc + sine_wave::<f64>(frequency * h.0)(t) * h.1 * envelope(t, attack, decay * h.2)
+        }) / 2.0;
+        T::from_f64(sample)
     }
 }
 
-pub fn organ(frequency: f64) -> impl Fn(f64) -> f64 {
+pub fn organ<T: Float>(frequency: f64) -> impl Fn(f64) -> T {
     move |t| {
         let frequency_2 = (frequency / 2.0) * 3.0;
-        sine_wave(frequency)(t) + 0.2 * sine_wave(frequency_2)(t)
+        T::from_f64(sine_wave::<f64>(frequency)(t) + 0.2 * sine_wave::<f64>(frequency_2)(t))
     }
 }
 
@@ -97,48 +100,49 @@ pub fn organ(frequency: f64) -> impl Fn(f64) -> f64 {
 /// `sample_rate` in hertz (eg, `44_100.0`)
 ///
 /// ```
-/// use synthrs::wave;
+/// use core::source;
 ///
-/// let karplus_sawtooth_generator =
-///     |frequency: f64| {
-///         wave::karplus_strong(wave::sawtooth_wave(frequency), 0.01, 1.0, 0.9, 44_100.0)
-///     };
+/// let karplus_sawtooth_generator = |frequency: f64| {
+///     source::karplus_strong(source::sawtooth_wave::<f64>(frequency), 0.01, 1.0, 0.9, 44_100.0)
+/// };
 /// ```
-pub fn karplus_strong<F: Fn(f64) -> f64>(
+pub fn karplus_strong<T: Float, F: Fn(f64) -> T>(
     generator: F,
     attack: f64,
     decay: f64,
     sharpness: f64,
     sample_rate: f64,
-) -> impl Fn(f64) -> f64 {
+) -> impl Fn(f64) -> T {
     move |t| {
         let tick = 1.0 / sample_rate;
 
         // Instead of using delay_line_generator we manually unroll the loop here
-        (0..10usize).fold(0.0, |acc, i| {
-            acc + generator(t - tick * i as f64)
-                * envelope(tick * i as f64, attack, decay)
-                * sharpness.powf(i as f64)
+        (0..10usize).fold(T::zero(), |acc, i| {
+            let gain = envelope(tick * i as f64, attack, decay) * sharpness.powf(i as f64);
+            acc + generator(t - tick * i as f64) * T::from_f64(gain)
         })
     }
 }
 
-pub fn white_noise() -> impl Fn(f64) -> f64 {
-    move |_t| rand::random::<f64>()
+pub fn white_noise<T: Float>() -> impl Fn(f64) -> T {
+    move |_t| T::from_f64(rand::random::<f64>())
 }
 
-pub fn gaussian_noise() -> impl Fn(f64) -> f64 {
+pub fn gaussian_noise<T: Float>() -> impl Fn(f64) -> T {
     move |_t| {
         let mut s = 0.0;
         for c in 0..16 {
             s += rand::random::<f64>()
         }
-        return (s - 8.0) / 4.0;
+        return T::from_f64((s - 8.0) / 4.0);
     }
 }
 
-/// Liljencrants-Fant glottal waveform model.
-pub fn liljencrants_fant(tenseness: f64) -> impl Fn(f64) -> f64 {
+/// Returns the Liljencrants-Fant timing parameters `(ta, tp, te)` for a given tenseness,
+/// normalised to a period of 1.
+///
+/// `tp` is the instant of maximum glottal flow and `te` the instant of closure.
+pub fn liljencrants_fant_timing(tenseness: f64) -> (f64, f64, f64) {
     // convert tenseness to rd variable
     let rd = 0.5 + 2.2 * (1.0 - tenseness); // must be in range: [.5, 2.7]
 
@@ -151,29 +155,68 @@ pub fn liljencrants_fant(tenseness: f64) -> impl Fn(f64) -> f64 {
     let tp = 1.0 / (2.0 * rg); // instant of maximum glottal flow
     let te = tp + tp * rk;
 
-    let epsilon = 1.0 / ta;
-    let shift = (-epsilon * (1.0 - te)).exp();
-    let delta = 1.0 - shift; // divide by this to scale RHS
+    (ta, tp, te)
+}
+
+/// Liljencrants-Fant glottal waveform model.
+pub fn liljencrants_fant<T: Float>(tenseness: f64) -> impl Fn(T) -> T {
+    let model = LiljencrantsFant::new(tenseness);
+    move |t| model.evaluate(t)
+}
+
+/// The precomputed coefficients of a Liljencrants-Fant glottal waveform.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LiljencrantsFant<T: Float = f64> {
+    /// The tenseness from which the model was derived.
+    pub tenseness: f64,
+    te: T,
+    epsilon: T,
+    shift: T,
+    delta: T,
+    omega: T,
+    alpha: T,
+    e0: T,
+}
 
-    let rhs_integral = ((1.0 / epsilon) * (shift - 1.0) + (1.0 - te) * shift) / delta;
+impl<T: Float> LiljencrantsFant<T> {
+    pub fn new(tenseness: f64) -> LiljencrantsFant<T> {
+        let (ta, tp, te) = liljencrants_fant_timing(tenseness);
 
-    let total_lower_integral = -(te - tp) / 2.0 + rhs_integral;
-    let total_upper_integral = -total_lower_integral;
+        let epsilon = 1.0 / ta;
+        let shift = (-epsilon * (1.0 - te)).exp();
+        let delta = 1.0 - shift; // divide by this to scale RHS
 
-    let omega = PI / tp;
-    let sine = (omega * te).sin();
+        let rhs_integral = ((1.0 / epsilon) * (shift - 1.0) + (1.0 - te) * shift) / delta;
 
-    let y = -PI * sine * total_upper_integral / (tp * 2.0);
-    let z = y.ln();
-    let alpha = z / (tp / 2.0 - te);
-    let e0 = -1.0 / (sine * (alpha * te).exp());
+        let total_lower_integral = -(te - tp) / 2.0 + rhs_integral;
+        let total_upper_integral = -total_lower_integral;
 
-    // return glottal waveform function
-    move |t| {
-        if t > te {
-            (-((-epsilon * (t - te)).exp()) + shift) / delta
+        let omega = PI / tp;
+        let sine = (omega * te).sin();
+
+        let y = -PI * sine * total_upper_integral / (tp * 2.0);
+        let z = y.ln();
+        let alpha = z / (tp / 2.0 - te);
+        let e0 = -1.0 / (sine * (alpha * te).exp());
+
+        LiljencrantsFant {
+            tenseness,
+            te: T::from_f64(te),
+            epsilon: T::from_f64(epsilon),
+            shift: T::from_f64(shift),
+            delta: T::from_f64(delta),
+            omega: T::from_f64(omega),
+            alpha: T::from_f64(alpha),
+            e0: T::from_f64(e0),
+        }
+    }
+
+    /// Returns the glottal waveform at phase `t` in range [0-1].
+    pub fn evaluate(&self, t: T) -> T {
+        if t > self.te {
+            (-((-self.epsilon * (t - self.te)).exp()) + self.shift) / self.delta
         } else {
-            e0 * (alpha * t).exp() * (omega * t).sin()
+            self.e0 * (self.alpha * t).exp() * (self.omega * t).sin()
         }
     }
 }
@@ -228,19 +271,19 @@ pub fn sampler(
 /// `sample_rate`: The sample rate of the given sample
 ///
 /// ```
-/// use synthrs::wave;
+/// use core::source;
 ///
 /// // This creates a sine wave that's delayed by 1 second
-/// let generator = wave::sine_wave(440.0);
-/// let delayed_sine = wave::delay_line_generator(generator, 1.0, 44_100);
+/// let generator = source::sine_wave::<f64>(440.0);
+/// let delayed_sine = source::delay_line_generator(generator, 1.0, 44_100);
 /// ```
-pub fn delay_line_generator<F: Fn(f64) -> f64>(
+pub fn delay_line_generator<T: Float, F: Fn(f64) -> T>(
     generator: F,
     delay_length: f64,
     sample_rate: usize,
-) -> impl Fn(f64) -> f64 {
+) -> impl Fn(f64) -> T {
     let delay_length_samples = (delay_length * sample_rate as f64).floor() as usize;
-    let buf: VecDeque<f64> = VecDeque::with_capacity(delay_length_samples + 1);
+    let buf: VecDeque<T> = VecDeque::with_capacity(delay_length_samples + 1);
     let cell = std::cell::RefCell::new(buf);
 
     move |t| {
@@ -248,9 +291,9 @@ pub fn delay_line_generator<F: Fn(f64) -> f64>(
         let current_sample = generator(t);
 
         let output = if buf.len() < delay_length_samples {
-            0.0f64
+            T::zero()
         } else {
-            buf.pop_front().unwrap_or(0.0f64)
+            buf.pop_front().unwrap_or_else(T::zero)
         };
 
         buf.push_back(current_sample);
@@ -264,11 +307,11 @@ pub fn delay_line_generator<F: Fn(f64) -> f64>(
 ///
 /// This is mainly an example on how to do stateful generator functions.
 /// This is achieved using interior mutability. See the source for details on how this is achieved.
-pub fn rising_linear(
+pub fn rising_linear<T: Float>(
     start_frequency: f64,
     end_frequency: f64,
     increment_per_sample: f64,
-) -> impl Fn(f64) -> f64 {
+) -> impl Fn(f64) -> T {
     // Our state! You can use a `RefCell` or a `Cell` for a start.
     // This example uses a `RefCell`, but a `Cell` will be simpler and
     // suffice for this simple state. An example using `Cell` is provided below.
@@ -334,5 +377,6 @@ mod tests {
         let lf = liljencrants_fant(0.0);
 
         assert_eq!(lf(0.0), 0.0);
+        assert_eq!(liljencrants_fant(0.0)(0.0f32), 0.0);
     }
 }
@@ -0,0 +1,731 @@
+//! English grapheme-to-phoneme conversion.
+//!
+//! Words are looked up in a CMUdict-format pronunciation dictionary, falling back to the
+//! letter-to-sound rules of Elovitz et al. (NRL report 7948, 1976) for unknown words.
+//! Pronunciations are emitted as the IPA symbols understood by [`Phoneme::from_ipa`].
+
+use super::Phoneme;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+/// The symbol emitted for a pause between phrases.
+const PAUSE: &str = "_";
+/// Punctuation at which a pause is emitted.
+const PUNCTUATION: &str = ".,;:!?";
+/// Letters treated as vowels by the letter-to-sound rules.
+const VOWELS: &str = "AEIOU";
+/// Consonants treated as voiced by the letter-to-sound rules.
+const VOICED: &str = "BDVGJLMNRWZ";
+/// Suffixes matched by `%` in the letter-to-sound rules.
+const SUFFIXES: [&str; 6] = ["ING", "ELY", "ER", "ES", "ED", "E"];
+
+/// Every ARPAbet symbol (without stress) with its IPA symbols.
+///
+/// Diphthongs and r-coloured vowels, which the tract has no single target for, are split into
+/// two phonemes.
+const ARPABET: [(&str, &[&str]); 49] = [
+    ("AA", &["ɑ"]),
+    ("AE", &["æ"]),
+    ("AH", &["ʌ"]),
+    ("AO", &["ɔ"]),
+    ("AW", &["a", "ʊ"]),
+    ("AX", &["ə"]),
+    ("AXR", &["ə", "ɹ"]),
+    ("AY", &["a", "ɪ"]),
+    ("EH", &["ɛ"]),
+    ("ER", &["ə", "ɹ"]),
+    ("EY", &["e", "ɪ"]),
+    ("IH", &["ɪ"]),
+    ("IX", &["ɪ"]),
+    ("IY", &["i"]),
+    ("OW", &["o", "ʊ"]),
+    ("OY", &["ɔ", "ɪ"]),
+    ("UH", &["ʊ"]),
+    ("UW", &["u"]),
+    ("UX", &["u"]),
+    ("B", &["b"]),
+    ("CH", &["tʃ"]),
+    ("D", &["d"]),
+    ("DH", &["ð"]),
+    ("DX", &["d"]),
+    ("EL", &["ə", "l"]),
+    ("EM", &["ə", "m"]),
+    ("EN", &["ə", "n"]),
+    ("F", &["f"]),
+    ("G", &["g"]),
+    ("HH", &["h"]),
+    ("JH", &["dʒ"]),
+    ("K", &["k"]),
+    ("L", &["l"]),
+    ("M", &["m"]),
+    ("N", &["n"]),
+    ("NG", &["ŋ"]),
+    ("NX", &["ŋ"]),
+    ("P", &["p"]),
+    ("Q", &["ʔ"]),
+    ("R", &["ɹ"]),
+    ("S", &["s"]),
+    ("SH", &["ʃ"]),
+    ("T", &["t"]),
+    ("TH", &["θ"]),
+    ("V", &["v"]),
+    ("W", &["w"]),
+    ("Y", &["j"]),
+    ("Z", &["z"]),
+    ("ZH", &["ʒ"]),
+];
+
+/// Returns the IPA symbols of an ARPAbet symbol, which may carry a stress digit.
+///
+/// Unstressed `AH0` is reduced to a schwa.
+pub fn arpabet_to_ipa(symbol: &str) -> Option<&'static [&'static str]> {
+    let upper = symbol.to_ascii_uppercase();
+    if upper == "AH0" {
+        return Some(&["ə"]);
+    }
+    let base = upper.trim_end_matches(|c: char| c.is_ascii_digit());
+    ARPABET
+        .iter()
+        .find(|entry| entry.0 == base)
+        .map(|entry| entry.1)
+}
+
+/// The letter-to-sound rules as (left context, letters, right context, ARPAbet), tried in order
+/// for the first letter of the remaining word.
+///
+/// In contexts, ` ` is a word boundary, `#` one or more vowels, `:` zero or more consonants,
+/// `^` one consonant, `+` a front vowel (E, I or Y), `.` a voiced consonant and `%` a suffix.
+#[rustfmt::skip]
+const RULES: &[(&str, &str, &str, &str)] = &[
+    ("", "A", " ", "AX"),
+    (" ", "ARE", " ", "AA R"),
+    (" ", "AR", "O", "AX R"),
+    ("", "AR", "#", "EH R"),
+    ("^", "AS", "#", "EY S"),
+    ("", "A", "WA", "AX"),
+    ("", "AW", "", "AO"),
+    (" :", "ANY", "", "EH N IY"),
+    ("", "A", "^+#", "EY"),
+    ("#:", "ALLY", "", "AX L IY"),
+    (" ", "AL", "#", "AX L"),
+    ("", "AGAIN", "", "AX G EH N"),
+    ("#:", "AG", "E", "IH JH"),
+    ("", "A", "^+:#", "AE"),
+    (" :", "A", "^+ ", "EY"),
+    ("", "A", "^%", "EY"),
+    (" ", "ARR", "", "AX R"),
+    ("", "ARR", "", "AE R"),
+    (" :", "AR", " ", "AA R"),
+    ("", "AR", " ", "ER"),
+    ("", "AR", "", "AA R"),
+    ("", "AIR", "", "EH R"),
+    ("", "AI", "", "EY"),
+    ("", "AY", "", "EY"),
+    ("", "AU", "", "AO"),
+    ("#:", "AL", " ", "AX L"),
+    ("#:", "ALS", " ", "AX L Z"),
+    ("", "ALK", "", "AO K"),
+    ("", "AL", "^", "AO L"),
+    (" :", "ABLE", "", "EY B AX L"),
+    ("", "ABLE", "", "AX B AX L"),
+    ("", "ANG", "+", "EY N JH"),
+    ("", "A", "", "AE"),
+    (" ", "BE", "^#", "B IH"),
+    ("", "BEING", "", "B IY IH NG"),
+    (" ", "BOTH", " ", "B OW TH"),
+    (" ", "BUS", "#", "B IH Z"),
+    ("", "BUIL", "", "B IH L"),
+    ("", "B", "", "B"),
+    (" ", "CH", "^", "K"),
+    ("^E", "CH", "", "K"),
+    ("", "CH", "", "CH"),
+    (" S", "CI", "#", "S AY"),
+    ("", "CI", "A", "SH"),
+    ("", "CI", "O", "SH"),
+    ("", "CI", "EN", "SH"),
+    ("", "C", "+", "S"),
+    ("", "CK", "", "K"),
+    ("", "COM", "%", "K AH M"),
+    ("", "C", "", "K"),
+    ("#:", "DED", " ", "D IH D"),
+    (".E", "D", " ", "D"),
+    ("#:^E", "D", " ", "T"),
+    (" ", "DE", "^#", "D IH"),
+    (" ", "DO", " ", "D UW"),
+    (" ", "DOES", "", "D AH Z"),
+    (" ", "DOING", "", "D UW IH NG"),
+    (" ", "DOW", "", "D AW"),
+    ("", "DU", "A", "JH UW"),
+    ("", "D", "", "D"),
+    ("#:", "E", " ", ""),
+    (" :", "E", " ", "IY"),
+    ("#", "ED", " ", "D"),
+    ("#:", "E", "D ", ""),
+    ("", "EV", "ER", "EH V"),
+    ("", "E", "^%", "IY"),
+    ("", "ERI", "#", "IY R IY"),
+    ("", "ERI", "", "EH R IH"),
+    ("#:", "ER", "#", "ER"),
+    ("", "ER", "#", "EH R"),
+    ("", "ER", "", "ER"),
+    (" ", "EVEN", "", "IY V EH N"),
+    ("#:", "E", "W", ""),
+    ("T", "EW", "", "UW"),
+    ("S", "EW", "", "UW"),
+    ("R", "EW", "", "UW"),
+    ("D", "EW", "", "UW"),
+    ("L", "EW", "", "UW"),
+    ("Z", "EW", "", "UW"),
+    ("N", "EW", "", "UW"),
+    ("J", "EW", "", "UW"),
+    ("TH", "EW", "", "UW"),
+    ("CH", "EW", "", "UW"),
+    ("SH", "EW", "", "UW"),
+    ("", "EW", "", "Y UW"),
+    ("", "E", "O", "IY"),
+    ("#:S", "ES", " ", "IH Z"),
+    ("#:C", "ES", " ", "IH Z"),
+    ("#:G", "ES", " ", "IH Z"),
+    ("#:Z", "ES", " ", "IH Z"),
+    ("#:X", "ES", " ", "IH Z"),
+    ("#:J", "ES", " ", "IH Z"),
+    ("#:CH", "ES", " ", "IH Z"),
+    ("#:SH", "ES", " ", "IH Z"),
+    ("#:", "E", "S ", ""),
+    ("#:", "ELY", " ", "L IY"),
+    ("#:", "EMENT", "", "M EH N T"),
+    ("", "EFUL", "", "F UH L"),
+    ("", "EE", "", "IY"),
+    ("", "EARN", "", "ER N"),
+    (" ", "EAR", "^", "ER"),
+    ("", "EAD", "", "EH D"),
+    ("#:", "EA", " ", "IY AX"),
+    ("", "EA", "SU", "EH"),
+    ("", "EA", "", "IY"),
+    ("", "EIGH", "", "EY"),
+    ("", "EI", "", "IY"),
+    (" ", "EYE", "", "AY"),
+    ("", "EY", "", "IY"),
+    ("", "EU", "", "Y UW"),
+    ("", "E", "", "EH"),
+    ("", "FUL", "", "F UH L"),
+    ("", "F", "", "F"),
+    ("", "GIV", "", "G IH V"),
+    (" ", "G", "I^", "G"),
+    ("", "GE", "T", "G EH"),
+    ("SU", "GGES", "", "G JH EH S"),
+    ("", "GG", "", "G"),
+    (" B#", "G", "", "G"),
+    ("", "G", "+", "JH"),
+    ("", "GREAT", "", "G R EY T"),
+    ("#", "GH", "", ""),
+    ("", "G", "", "G"),
+    (" ", "HAV", "", "HH AE V"),
+    (" ", "HERE", "", "HH IY R"),
+    (" ", "HOUR", "", "AW ER"),
+    ("", "HOW", "", "HH AW"),
+    ("", "H", "#", "HH"),
+    ("", "H", "", ""),
+    (" ", "IN", "", "IH N"),
+    (" ", "I", " ", "AY"),
+    ("", "IN", "D", "AY N"),
+    ("", "IER", "", "IY ER"),
+    ("#:R", "IED", "", "IY D"),
+    ("", "IED", " ", "AY D"),
+    ("", "IEN", "", "IY EH N"),
+    ("", "IE", "T", "AY EH"),
+    (" :", "I", "%", "AY"),
+    ("", "I", "%", "IY"),
+    ("", "IE", "", "IY"),
+    ("", "I", "^+:#", "IH"),
+    ("", "IR", "#", "AY R"),
+    ("", "IZ", "%", "AY Z"),
+    ("", "IS", "%", "AY Z"),
+    ("", "I", "D%", "AY"),
+    ("+^", "I", "^+", "IH"),
+    ("", "I", "T%", "AY"),
+    ("#:^", "I", "^+", "IH"),
+    ("", "I", "^+", "AY"),
+    ("", "IR", "", "ER"),
+    ("", "IGH", "", "AY"),
+    ("", "ILD", "", "AY L D"),
+    ("", "IGN", " ", "AY N"),
+    ("", "IGN", "^", "AY N"),
+    ("", "IGN", "%", "AY N"),
+    ("", "IQUE", "", "IY K"),
+    ("", "I", "", "IH"),
+    ("", "J", "", "JH"),
+    (" ", "K", "N", ""),
+    ("", "K", "", "K"),
+    ("", "LO", "C#", "L OW"),
+    ("L", "L", "", ""),
+    ("#:^", "L", "%", "AX L"),
+    ("", "LEAD", "", "L IY D"),
+    ("", "L", "", "L"),
+    ("", "MOV", "", "M UW V"),
+    ("", "M", "", "M"),
+    ("E", "NG", "+", "N JH"),
+    ("", "NG", "R", "NG G"),
+    ("", "NG", "#", "NG G"),
+    ("", "NGL", "%", "NG G AX L"),
+    ("", "NG", "", "NG"),
+    ("", "NK", "", "NG K"),
+    (" ", "NOW", " ", "N AW"),
+    ("", "N", "", "N"),
+    ("", "OF", " ", "AX V"),
+    ("", "OROUGH", "", "ER OW"),
+    ("#:", "OR", " ", "ER"),
+    ("#:", "ORS", " ", "ER Z"),
+    ("", "OR", "", "AO R"),
+    (" ", "ONE", "", "W AH N"),
+    ("", "OW", "", "OW"),
+    (" ", "OVER", "", "OW V ER"),
+    ("", "OV", "", "AH V"),
+    ("", "O", "^%", "OW"),
+    ("", "O", "^EN", "OW"),
+    ("", "O", "^I#", "OW"),
+    ("", "OL", "D", "OW L"),
+    ("", "OUGHT", "", "AO T"),
+    ("", "OUGH", "", "AH F"),
+    (" ", "OU", "", "AW"),
+    ("H", "OU", "S#", "AW"),
+    ("", "OUS", "", "AX S"),
+    ("", "OUR", "", "AO R"),
+    ("", "OULD", "", "UH D"),
+    ("^", "OU", "^L", "AH"),
+    ("", "OUP", "", "UW P"),
+    ("", "OU", "", "AW"),
+    ("", "OY", "", "OY"),
+    ("", "OING", "", "OW IH NG"),
+    ("", "OI", "", "OY"),
+    ("", "OOR", "", "AO R"),
+    ("", "OOK", "", "UH K"),
+    ("", "OOD", "", "UH D"),
+    ("", "OO", "", "UW"),
+    ("", "O", "E", "OW"),
+    ("", "O", " ", "OW"),
+    ("", "OA", "", "OW"),
+    (" ", "ONLY", "", "OW N L IY"),
+    (" ", "ONCE", "", "W AH N S"),
+    ("C", "O", "N", "AA"),
+    ("", "O", "NG", "AO"),
+    (" :^", "O", "N", "AH"),
+    ("I", "ON", "", "AX N"),
+    ("#:", "ON", " ", "AX N"),
+    ("#^", "ON", "", "AX N"),
+    ("", "O", "ST ", "OW"),
+    ("", "OF", "^", "AO F"),
+    ("", "OTHER", "", "AH DH ER"),
+    ("", "OSS", " ", "AO S"),
+    ("#:^", "OM", "", "AH M"),
+    ("", "O", "", "AA"),
+    ("", "PH", "", "F"),
+    ("", "PEOP", "", "P IY P"),
+    ("", "POW", "", "P AW"),
+    ("", "PUT", " ", "P UH T"),
+    ("", "P", "", "P"),
+    ("", "QUAR", "", "K W AO R"),
+    ("", "QU", "", "K W"),
+    ("", "Q", "", "K"),
+    (" ", "RE", "^#", "R IY"),
+    ("", "R", "", "R"),
+    ("", "SH", "", "SH"),
+    ("#", "SION", "", "ZH AX N"),
+    ("", "SOME", "", "S AH M"),
+    ("#", "SUR", "#", "ZH ER"),
+    ("", "SUR", "#", "SH ER"),
+    ("#", "SU", "#", "ZH UW"),
+    ("#", "SSU", "#", "SH UW"),
+    ("#", "SED", " ", "Z D"),
+    ("#", "S", "#", "Z"),
+    ("", "SAID", "", "S EH D"),
+    ("^", "SION", "", "SH AX N"),
+    ("", "S", "S", ""),
+    (".", "S", " ", "Z"),
+    ("#:.E", "S", " ", "Z"),
+    ("#:^##", "S", " ", "Z"),
+    ("#:^#", "S", " ", "S"),
+    ("U", "S", " ", "S"),
+    (" :#", "S", " ", "Z"),
+    (" ", "SCH", "", "S K"),
+    ("", "S", "C+", ""),
+    ("#", "SM", "", "Z M"),
+    ("", "S", "", "S"),
+    (" ", "THE", " ", "DH AX"),
+    ("", "TO", " ", "T UW"),
+    ("", "THAT", " ", "DH AE T"),
+    (" ", "THIS", " ", "DH IH S"),
+    (" ", "THEY", "", "DH EY"),
+    (" ", "THERE", "", "DH EH R"),
+    ("", "THER", "", "DH ER"),
+    ("", "THEIR", "", "DH EH R"),
+    (" ", "THAN", " ", "DH AE N"),
+    (" ", "THEM", " ", "DH EH M"),
+    ("", "THESE", " ", "DH IY Z"),
+    (" ", "THEN", "", "DH EH N"),
+    ("", "THROUGH", "", "TH R UW"),
+    ("", "THOSE", "", "DH OW Z"),
+    ("", "THOUGH", " ", "DH OW"),
+    (" ", "THUS", "", "DH AH S"),
+    ("", "TH", "", "TH"),
+    ("#:", "TED", " ", "T IH D"),
+    ("S", "TI", "#N", "CH"),
+    ("", "TI", "O", "SH"),
+    ("", "TI", "A", "SH"),
+    ("", "TIEN", "", "SH AX N"),
+    ("", "TUR", "#", "CH ER"),
+    ("", "TU", "A", "CH UW"),
+    (" ", "TWO", "", "T UW"),
+    ("", "T", "", "T"),
+    (" ", "UN", "I", "Y UW N"),
+    (" ", "UN", "", "AH N"),
+    (" ", "UPON", "", "AX P AO N"),
+    ("T", "UR", "#", "UH R"),
+    ("S", "UR", "#", "UH R"),
+    ("R", "UR", "#", "UH R"),
+    ("D", "UR", "#", "UH R"),
+    ("L", "UR", "#", "UH R"),
+    ("Z", "UR", "#", "UH R"),
+    ("N", "UR", "#", "UH R"),
+    ("J", "UR", "#", "UH R"),
+    ("TH", "UR", "#", "UH R"),
+    ("CH", "UR", "#", "UH R"),
+    ("SH", "UR", "#", "UH R"),
+    ("", "UR", "#", "Y UH R"),
+    ("", "UR", "", "ER"),
+    ("", "U", "^ ", "AH"),
+    ("", "U", "^^", "AH"),
+    ("", "UY", "", "AY"),
+    (" G", "U", "#", ""),
+    ("G", "U", "%", ""),
+    ("G", "U", "#", "W"),
+    ("#N", "U", "", "Y UW"),
+    ("T", "U", "", "UW"),
+    ("S", "U", "", "UW"),
+    ("R", "U", "", "UW"),
+    ("D", "U", "", "UW"),
+    ("L", "U", "", "UW"),
+    ("Z", "U", "", "UW"),
+    ("N", "U", "", "UW"),
+    ("J", "U", "", "UW"),
+    ("TH", "U", "", "UW"),
+    ("CH", "U", "", "UW"),
+    ("SH", "U", "", "UW"),
+    ("", "U", "", "Y UW"),
+    ("", "VIEW", "", "V Y UW"),
+    ("", "V", "", "V"),
+    (" ", "WERE", "", "W ER"),
+    ("", "WA", "S", "W AA"),
+    ("", "WA", "T", "W AA"),
+    ("", "WHERE", "", "W EH R"),
+    ("", "WHAT", "", "W AA T"),
+    ("", "WHOL", "", "HH OW L"),
+    ("", "WHO", "", "HH UW"),
+    ("", "WH", "", "W"),
+    ("", "WAR", "", "W AO R"),
+    ("", "WOR", "^", "W ER"),
+    ("", "WR", "", "R"),
+    ("", "W", "", "W"),
+    ("", "X", "", "K S"),
+    ("", "YOUNG", "", "Y AH NG"),
+    (" ", "YOU", "", "Y UW"),
+    (" ", "YES", "", "Y EH S"),
+    (" ", "Y", "", "Y"),
+    ("#:^", "Y", " ", "IY"),
+    ("#:^", "Y", "I", "IY"),
+    (" :", "Y", " ", "AY"),
+    (" :", "Y", "#", "AY"),
+    (" :", "Y", "^+:#", "IH"),
+    (" :", "Y", "^#", "AY"),
+    ("", "Y", "", "IH"),
+    ("", "Z", "", "Z"),
+];
+
+fn is_vowel(letter: u8) -> bool {
+    VOWELS.as_bytes().contains(&letter)
+}
+
+fn is_consonant(letter: u8) -> bool {
+    letter.is_ascii_uppercase() && !is_vowel(letter)
+}
+
+/// Returns whether a right context matches the letters of `word` from `start`.
+fn matches_right(context: &[u8], word: &[u8], start: usize) -> bool {
+    let (&symbol, rest) = match context.split_first() {
+        Some(split) => split,
+        None => return true,
+    };
+    let letter = word.get(start).copied().unwrap_or(b' ');
+    let tail = word.get(start..).unwrap_or_default();
+    match symbol {
+        b' ' => !letter.is_ascii_uppercase() && matches_right(rest, word, start + 1),
+        b'#' => {
+            let count = tail.iter().take_while(|&&l| is_vowel(l)).count();
+            (1..=count).any(|count| matches_right(rest, word, start + count))
+        }
+        b':' => {
+            let count = tail.iter().take_while(|&&l| is_consonant(l)).count();
+            (0..=count).any(|count| matches_right(rest, word, start + count))
+        }
+        b'^' => is_consonant(letter) && matches_right(rest, word, start + 1),
+        b'+' => b"EIY".contains(&letter) && matches_right(rest, word, start + 1),
+        b'.' => VOICED.as_bytes().contains(&letter) && matches_right(rest, word, start + 1),
+        b'%' => SUFFIXES.iter().any(|suffix| {
+            tail.starts_with(suffix.as_bytes()) && matches_right(rest, word, start + suffix.len())
+        }),
+        _ => letter == symbol && matches_right(rest, word, start + 1),
+    }
+}
+
+/// Returns whether a left context matches the letters of `word` before `end`.
+fn matches_left(context: &[u8], word: &[u8], end: usize) -> bool {
+    let (&symbol, rest) = match context.split_last() {
+        Some(split) => split,
+        None => return true,
+    };
+    let letter = end.checked_sub(1).map_or(b' ', |index| word[index]);
+    let before = end.saturating_sub(1);
+    match symbol {
+        b' ' => !letter.is_ascii_uppercase() && matches_left(rest, word, before),
+        b'#' => {
+            let count = word[..end]
+                .iter()
+                .rev()
+                .take_while(|&&l| is_vowel(l))
+                .count();
+            (1..=count).any(|count| matches_left(rest, word, end - count))
+        }
+        b':' => {
+            let count = word[..end]
+                .iter()
+                .rev()
+                .take_while(|&&l| is_consonant(l))
+                .count();
+            (0..=count).any(|count| matches_left(rest, word, end - count))
+        }
+        b'^' => is_consonant(letter) && matches_left(rest, word, before),
+        b'+' => b"EIY".contains(&letter) && matches_left(rest, word, before),
+        b'.' => VOICED.as_bytes().contains(&letter) && matches_left(rest, word, before),
+        _ => letter == symbol && matches_left(rest, word, before),
+    }
+}
+
+/// Returns the ARPAbet pronunciation of a word by the letter-to-sound rules.
+///
+/// Characters other than letters are ignored.
+pub fn letter_to_sound(word: &str) -> Vec<&'static str> {
+    let word: Vec<u8> = word
+        .bytes()
+        .filter(u8::is_ascii_alphabetic)
+        .map(|letter| letter.to_ascii_uppercase())
+        .collect();
+
+    let mut phones = Vec::new();
+    let mut index = 0;
+    while index < word.len() {
+        let rule = RULES.iter().find(|&&(left, letters, right, _)| {
+            word[index..].starts_with(letters.as_bytes())
+                && matches_left(left.as_bytes(), &word, index)
+                && matches_right(right.as_bytes(), &word, index + letters.len())
+        });
+        match rule {
+            Some(&(_, letters, _, output)) => {
+                phones.extend(output.split_whitespace());
+                index += letters.len();
+            }
+            // every letter has a rule without context
+            None => index += 1,
+        }
+    }
+    phones
+}
+
+/// A pronunciation dictionary in the format of the CMU Pronouncing Dictionary.
+///
+/// Each line holds a word followed by its ARPAbet pronunciation, with alternative
+/// pronunciations marked `WORD(2)` and comments starting with `;;;` or `#`. Only the first
+/// pronunciation of each word is kept.
+#[derive(Clone, Debug, Default)]
+pub struct Dictionary {
+    /// The IPA pronunciation of each (lowercase) word.
+    words: HashMap<String, Vec<&'static str>>,
+}
+
+impl Dictionary {
+    /// Creates an empty dictionary.
+    pub fn new() -> Dictionary {
+        Dictionary::default()
+    }
+
+    /// Loads a dictionary from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Dictionary> {
+        Dictionary::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Reads a dictionary, failing on a pronunciation with an unknown ARPAbet symbol.
+    pub fn read<R: BufRead>(mut reader: R) -> io::Result<Dictionary> {
+        let mut dictionary = Dictionary::new();
+        let mut buffer = Vec::new();
+        let mut number = 0;
+        // older releases of the dictionary are not valid UTF-8
+        while reader.read_until(b'\n', &mut buffer)? > 0 {
+            number += 1;
+            let line = String::from_utf8_lossy(&buffer);
+            let line = line.split('#').next().unwrap_or_default();
+            if !line.starts_with(";;;") {
+                let mut fields = line.split_whitespace();
+                if let Some(word) = fields.next() {
+                    let word = word.split('(').next().unwrap_or(word).to_lowercase();
+                    let mut pronunciation = Vec::new();
+                    for symbol in fields {
+                        let ipa = arpabet_to_ipa(symbol).ok_or_else(|| {
+                            io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                format!("unknown symbol {} on line {}", symbol, number),
+                            )
+                        })?;
+                        pronunciation.extend_from_slice(ipa);
+                    }
+                    dictionary.words.entry(word).or_insert(pronunciation);
+                }
+            }
+            buffer.clear();
+        }
+        Ok(dictionary)
+    }
+
+    /// Adds or replaces the IPA pronunciation of a word.
+    pub fn insert(&mut self, word: &str, pronunciation: Vec<&'static str>) {
+        self.words.insert(word.to_lowercase(), pronunciation);
+    }
+
+    /// Returns the IPA pronunciation of a word, if it is in the dictionary.
+    pub fn get(&self, word: &str) -> Option<&[&'static str]> {
+        self.words.get(&word.to_lowercase()).map(Vec::as_slice)
+    }
+
+    /// Returns the number of words in the dictionary.
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Returns whether the dictionary is empty.
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+/// Converts English text to phonemes, by dictionary where possible and otherwise by rule.
+#[derive(Clone, Debug, Default)]
+pub struct G2p {
+    dictionary: Dictionary,
+}
+
+impl G2p {
+    pub fn new(dictionary: Dictionary) -> G2p {
+        G2p { dictionary }
+    }
+
+    /// Returns the dictionary of known pronunciations.
+    pub fn dictionary(&self) -> &Dictionary {
+        &self.dictionary
+    }
+
+    /// Returns the IPA pronunciation of a single word.
+    pub fn word(&self, word: &str) -> Vec<&'static str> {
+        match self.dictionary.get(word) {
+            Some(pronunciation) => pronunciation.to_vec(),
+            None => letter_to_sound(word)
+                .into_iter()
+                .flat_map(|phone| arpabet_to_ipa(phone).unwrap_or_default())
+                .copied()
+                .collect(),
+        }
+    }
+
+    /// Returns the IPA symbols of a text, with a pause (`_`) at each punctuation mark.
+    pub fn transcribe(&self, text: &str) -> Vec<&'static str> {
+        let mut symbols = Vec::new();
+        let mut word = String::new();
+        for character in text.chars().chain(Some(' ')) {
+            if character.is_alphabetic() || character == '\'' {
+                word.push(character);
+                continue;
+            }
+            if !word.is_empty() {
+                symbols.extend(self.word(&word));
+                word.clear();
+            }
+            if PUNCTUATION.contains(character) && symbols.last() != Some(&PAUSE) {
+                symbols.push(PAUSE);
+            }
+        }
+        symbols
+    }
+
+    /// Returns the phonemes of a text (see [`G2p::transcribe`]).
+    pub fn phonemes(&self, text: &str) -> Vec<Phoneme> {
+        self.transcribe(text)
+            .into_iter()
+            .filter_map(Phoneme::from_ipa)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_arpabet_to_ipa() {
+        assert_eq!(arpabet_to_ipa("AH0"), Some(&["ə"][..]));
+        assert_eq!(arpabet_to_ipa("ah1"), Some(&["ʌ"][..]));
+        assert_eq!(arpabet_to_ipa("AY2"), Some(&["a", "ɪ"][..]));
+        assert_eq!(arpabet_to_ipa("XX"), None);
+
+        // every symbol is a phoneme the sequencer understands
+        for (_, ipa) in ARPABET {
+            assert!(ipa.iter().all(|symbol| Phoneme::from_ipa(symbol).is_some()));
+        }
+    }
+
+    #[test]
+    fn test_letter_to_sound() {
+        assert_eq!(letter_to_sound("cat"), ["K", "AE", "T"]);
+        assert_eq!(letter_to_sound("shine"), ["SH", "AY", "N"]);
+        assert_eq!(letter_to_sound("phone"), ["F", "OW", "N"]);
+        assert_eq!(letter_to_sound("thing"), ["TH", "IH", "NG"]);
+        assert_eq!(letter_to_sound("rain"), ["R", "EY", "N"]);
+        assert_eq!(letter_to_sound("speak"), ["S", "P", "IY", "K"]);
+        assert_eq!(letter_to_sound("knight"), ["N", "AY", "T"]);
+
+        // every rule emits ARPAbet
+        for &(_, _, _, output) in RULES {
+            assert!(output
+                .split_whitespace()
+                .all(|p| arpabet_to_ipa(p).is_some()));
+        }
+    }
+
+    #[test]
+    fn test_dictionary() {
+        let file = ";;; a comment\n\
+                    HELLO  HH AH0 L OW1\n\
+                    HELLO(2)  HH EH0 L OW1\n\
+                    WORLD  W ER1 L D # a trailing comment\n";
+        let dictionary = Dictionary::read(Cursor::new(file)).unwrap();
+        assert_eq!(dictionary.len(), 2);
+        assert_eq!(dictionary.get("Hello").unwrap(), ["h", "ə", "l", "o", "ʊ"]);
+        assert!(Dictionary::read(Cursor::new("BAD  B XX D\n")).is_err());
+
+        let g2p = G2p::new(dictionary);
+        assert_eq!(
+            g2p.transcribe("Hello, world! Cat."),
+            ["h", "ə", "l", "o", "ʊ", "_", "w", "ə", "ɹ", "l", "d", "_", "k", "æ", "t", "_"]
+        );
+        assert_eq!(g2p.phonemes("hello world").len(), 10);
+    }
+}
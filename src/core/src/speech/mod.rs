@@ -0,0 +1,52 @@
+//! Speech and singing from sequences of phonemes.
+
+pub mod g2p;
+pub mod sequencer;
+
+use crate::filter::stateful::tract::consonants::Consonant;
+use crate::filter::stateful::tract::vowels::Vowel;
+
+pub use g2p::{Dictionary, G2p};
+pub use sequencer::{PhonemeEvent, Sequencer, SpeechFrame};
+
+/// The symbol of a pause.
+const SILENCE: &str = "_";
+
+/// A unit of speech.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Phoneme {
+    Vowel(Vowel),
+    Consonant(Consonant),
+    /// A pause, during which the voice is silent.
+    Silence,
+}
+
+impl Phoneme {
+    /// Returns the phoneme for a given IPA symbol (`_` being silence).
+    pub fn from_ipa(symbol: &str) -> Option<Phoneme> {
+        if symbol == SILENCE {
+            return Some(Phoneme::Silence);
+        }
+        Vowel::from_ipa(symbol)
+            .map(Phoneme::Vowel)
+            .or_else(|| Consonant::from_ipa(symbol).map(Phoneme::Consonant))
+    }
+
+    /// Returns the phoneme's IPA symbol, if it is in the library.
+    pub fn ipa(&self) -> Option<&'static str> {
+        match self {
+            Phoneme::Vowel(vowel) => Some(vowel.ipa()),
+            Phoneme::Consonant(consonant) => consonant.ipa(),
+            Phoneme::Silence => Some(SILENCE),
+        }
+    }
+
+    /// Returns whether the phoneme is voiced.
+    pub fn is_voiced(&self) -> bool {
+        match self {
+            Phoneme::Vowel(_) => true,
+            Phoneme::Consonant(consonant) => consonant.voiced,
+            Phoneme::Silence => false,
+        }
+    }
+}
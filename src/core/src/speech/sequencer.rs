@@ -0,0 +1,383 @@
+//! Scheduling of phonemes as overlapping articulatory gestures.
+//!
+//! Vowels set targets for the tongue and lips, between which the articulators glide through
+//! any intervening consonants (so a consonant is coloured by the vowels around it). Consonants
+//! are [`Gesture`]s superimposed on the vowels, beginning to close before their phoneme starts
+//! and releasing after it ends, so neighbouring gestures overlap.
+
+use super::Phoneme;
+use crate::filter::stateful::envelope::Curve;
+use crate::filter::stateful::tract::consonants::{Gesture, GestureFrame, Timing};
+use crate::filter::stateful::tract::vowels::{Articulation, Vowel};
+use crate::filter::stateful::tract::BLOCK_SIZE;
+use crate::float::Float;
+use crate::voice::{Parameter, Voice};
+
+/// The default time over which the tongue and lips move between vowels (seconds).
+const COARTICULATION: f64 = 0.12;
+/// The time over which pitch glides to each phoneme's pitch (seconds).
+const PITCH_GLIDE: f64 = 0.04;
+/// The velocity with which a sequence's note is started.
+const VELOCITY: u8 = 100;
+
+/// A phoneme to be spoken or sung.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhonemeEvent {
+    pub phoneme: Phoneme,
+    /// The duration of the phoneme in seconds.
+    pub duration: f64,
+    /// The pitch of the phoneme as a midi pitch (fractional pitches are allowed).
+    pub pitch: f64,
+}
+
+impl PhonemeEvent {
+    pub fn new(phoneme: Phoneme, duration: f64, pitch: f64) -> PhonemeEvent {
+        PhonemeEvent {
+            phoneme,
+            duration,
+            pitch,
+        }
+    }
+}
+
+/// The state of the voice at an instant of a sequence.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpeechFrame {
+    /// The position of the tongue and lips.
+    pub articulation: Articulation,
+    /// The combined constriction, velum, voicing and aspiration of every active consonant.
+    pub gesture: GestureFrame,
+    /// The pitch as a midi pitch.
+    pub pitch: f64,
+}
+
+/// The interval over which a vowel's target is held.
+#[derive(Clone, Copy, Debug)]
+struct Target {
+    start: f64,
+    end: f64,
+    articulation: Articulation,
+}
+
+/// Schedules a sequence of phonemes and drives a [`Voice`] through them.
+#[derive(Clone, Debug)]
+pub struct Sequencer {
+    events: Vec<PhonemeEvent>,
+    /// The start time of each event in seconds.
+    starts: Vec<f64>,
+    /// The vowel targets in time order.
+    targets: Vec<Target>,
+    /// The start time and gesture of each consonant in time order.
+    gestures: Vec<(f64, Gesture)>,
+    /// The duration of the longest gesture.
+    longest: f64,
+    /// The time over which the tongue and lips move between vowels (seconds).
+    coarticulation: f64,
+    /// The number of samples rendered.
+    clock: u64,
+}
+
+impl Sequencer {
+    pub fn new(events: Vec<PhonemeEvent>) -> Sequencer {
+        Sequencer::with_coarticulation(events, COARTICULATION)
+    }
+
+    /// Creates a sequencer whose articulators move between vowels over `coarticulation` seconds
+    /// (shortened for vowels too brief to allow it).
+    pub fn with_coarticulation(events: Vec<PhonemeEvent>, coarticulation: f64) -> Sequencer {
+        let mut starts = Vec::with_capacity(events.len());
+        let mut targets = Vec::new();
+        let mut gestures = Vec::new();
+        let mut time = 0.0;
+        for event in &events {
+            starts.push(time);
+            let end = time + event.duration;
+            match event.phoneme {
+                Phoneme::Vowel(vowel) => {
+                    let window = (coarticulation / 2.0).min(event.duration / 2.0);
+                    targets.push(Target {
+                        start: time + window,
+                        end: end - window,
+                        articulation: vowel.articulation(),
+                    });
+                }
+                Phoneme::Consonant(consonant) => {
+                    // close during the previous phoneme and release into the next
+                    let Timing {
+                        closure, release, ..
+                    } = consonant.manner.timing();
+                    let hold = (event.duration - (closure + release) / 2.0).max(0.0);
+                    let timing = Timing::new(closure, hold, release);
+                    gestures.push((time - closure / 2.0, Gesture::new(consonant, timing)));
+                }
+                Phoneme::Silence => (),
+            }
+            time = end;
+        }
+
+        // very brief consonants may begin closing before the previous one
+        gestures.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let longest = gestures
+            .iter()
+            .map(|(_, gesture)| gesture.timing.duration())
+            .fold(0.0, f64::max);
+        Sequencer {
+            events,
+            starts,
+            targets,
+            gestures,
+            longest,
+            coarticulation,
+            clock: 0,
+        }
+    }
+
+    /// Returns the events of the sequence.
+    pub fn events(&self) -> &[PhonemeEvent] {
+        &self.events
+    }
+
+    /// Returns the time over which the articulators move between vowels (seconds).
+    pub fn coarticulation(&self) -> f64 {
+        self.coarticulation
+    }
+
+    /// Returns the duration of the sequence in seconds.
+    pub fn duration(&self) -> f64 {
+        match (self.starts.last(), self.events.last()) {
+            (Some(start), Some(event)) => start + event.duration,
+            _ => 0.0,
+        }
+    }
+
+    /// Returns the index of the event at a time, if any.
+    fn event_at(&self, time: f64) -> Option<usize> {
+        let next = self.starts.partition_point(|&start| start <= time);
+        (next > 0 && time < self.duration()).then(|| next - 1)
+    }
+
+    /// Returns the position of the tongue and lips at a time.
+    fn articulation_at(&self, time: f64) -> Articulation {
+        let next = self.targets.partition_point(|target| target.end < time);
+        let target = match self.targets.get(next) {
+            Some(target) => target,
+            None => {
+                return self
+                    .targets
+                    .last()
+                    .map_or_else(|| Vowel::Schwa.articulation(), |target| target.articulation)
+            }
+        };
+        if next == 0 || time >= target.start {
+            return target.articulation;
+        }
+
+        let previous = &self.targets[next - 1];
+        let x = (time - previous.end) / (target.start - previous.end);
+        previous
+            .articulation
+            .lerp(&target.articulation, Curve::Smooth.shape(x))
+    }
+
+    /// Returns the state of the voice at a time in seconds.
+    pub fn frame_at(&self, time: f64) -> SpeechFrame {
+        // gestures may overlap, in which case the narrowest constriction wins
+        let mut gesture = GestureFrame::default();
+        let until = self.gestures.partition_point(|&(start, _)| start <= time);
+        let active = self.gestures[..until]
+            .iter()
+            .rev()
+            .take_while(|&&(start, _)| start + self.longest > time);
+        for (start, active) in active {
+            let frame = active.frame_at(time - start);
+            gesture.velum = gesture.velum.max(frame.velum);
+            gesture.voicing = gesture.voicing.min(frame.voicing);
+            gesture.aspiration = gesture.aspiration.max(frame.aspiration);
+            gesture.constriction = match (gesture.constriction, frame.constriction) {
+                (Some(a), Some(b)) if b.diameter < a.diameter => Some(b),
+                (a, b) => a.or(b),
+            };
+        }
+
+        let pitch = match self.event_at(time) {
+            Some(index) => {
+                if self.events[index].phoneme == Phoneme::Silence {
+                    gesture.voicing = 0.0;
+                }
+                let pitch = self.events[index].pitch;
+                match index
+                    .checked_sub(1)
+                    .map(|previous| self.events[previous].pitch)
+                {
+                    Some(previous) => {
+                        let x = (time - self.starts[index]) / PITCH_GLIDE;
+                        previous + (pitch - previous) * Curve::Smooth.shape(x)
+                    }
+                    None => pitch,
+                }
+            }
+            None => self.events.last().map_or(0.0, |event| event.pitch),
+        };
+
+        SpeechFrame {
+            articulation: self.articulation_at(time),
+            gesture,
+            pitch,
+        }
+    }
+
+    /// Returns the time of the sequencer's clock in seconds, at a sample rate.
+    pub fn time(&self, sample_rate: f64) -> f64 {
+        self.clock as f64 / sample_rate
+    }
+
+    /// Returns whether every phoneme has been rendered (the voice may still be releasing).
+    pub fn is_finished(&self, sample_rate: f64) -> bool {
+        self.time(sample_rate) >= self.duration()
+    }
+
+    /// Restarts the sequence.
+    pub fn rewind(&mut self) {
+        self.clock = 0;
+    }
+
+    /// Renders a block of the sequence through a voice, overwriting `output`.
+    ///
+    /// The voice's note starts with the first phoneme and is released after the last. Blocks
+    /// are split at the start of each phoneme so that its pitch and voicing take effect on its
+    /// first sample.
+    pub fn render_block<T: Float>(&mut self, voice: &mut Voice<T>, output: &mut [T]) {
+        let sample_rate = voice.sample_rate();
+        let end = (self.duration() * sample_rate).round() as u64;
+        let mut start = 0;
+        while start < output.len() {
+            let mut length = (output.len() - start).min(BLOCK_SIZE);
+            if self.clock < end {
+                if self.clock == 0 {
+                    let pitch = self.events[0].pitch.round().clamp(0.0, 127.0);
+                    voice.note_on(pitch as u8, VELOCITY);
+                }
+                let next = self.next_boundary(sample_rate).min(end);
+                length = length.min((next - self.clock) as usize);
+
+                let time = (self.clock + length as u64 - 1) as f64 / sample_rate;
+                let frame = self.frame_at(time);
+                voice.set_articulation(&frame.articulation);
+                voice.articulate(&frame.gesture);
+                voice.set_parameter(Parameter::Pitch, frame.pitch);
+            }
+
+            let chunk = &mut output[start..start + length];
+            voice.render_block(chunk);
+            start += length;
+
+            let rendered = self.clock < end;
+            self.clock += length as u64;
+            if rendered && self.clock >= end {
+                voice.note_off();
+            }
+        }
+    }
+
+    /// Returns the sample at which the next phoneme starts.
+    fn next_boundary(&self, sample_rate: f64) -> u64 {
+        self.starts
+            .iter()
+            .map(|start| (start * sample_rate).round() as u64)
+            .find(|&sample| sample > self.clock)
+            .unwrap_or(u64::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::stateful::tract::consonants::Consonant;
+
+    fn sequence(ipa: &[(&str, f64, f64)]) -> Sequencer {
+        Sequencer::new(
+            ipa.iter()
+                .map(|&(symbol, duration, pitch)| {
+                    PhonemeEvent::new(Phoneme::from_ipa(symbol).unwrap(), duration, pitch)
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_frame_at() {
+        let sequencer = sequence(&[
+            ("s", 0.1, 60.0),
+            ("a", 0.3, 60.0),
+            ("m", 0.1, 64.0),
+            ("i", 0.3, 64.0),
+            ("_", 0.1, 64.0),
+        ]);
+        assert!((sequencer.duration() - 0.9).abs() < 1e-9);
+
+        // a voiceless fricative
+        let s = sequencer.frame_at(0.05);
+        let constriction = s.gesture.constriction.unwrap();
+        assert!(constriction.fricative_intensity > 0.5);
+        assert!(s.gesture.voicing < 0.5);
+
+        // the vowel's target is reached and the fricative released
+        let a = sequencer.frame_at(0.25);
+        assert_eq!(a.articulation, Vowel::A.articulation());
+        assert_eq!(a.gesture.constriction, None);
+
+        // the nasal closes the lips as the tongue moves from a towards i
+        let m = sequencer.frame_at(0.45);
+        assert_eq!(m.gesture.constriction.unwrap().diameter, 0.0);
+        assert_eq!(m.gesture.velum, 1.0);
+        let (from, to) = (Vowel::A.articulation(), Vowel::I.articulation());
+        assert!(m.articulation.tongue_index > from.tongue_index);
+        assert!(m.articulation.tongue_index < to.tongue_index);
+
+        // the nasal begins closing before it starts
+        assert!(sequencer.frame_at(0.39).gesture.constriction.is_some());
+
+        // pitch glides into each phoneme
+        assert_eq!(sequencer.frame_at(0.4).pitch, 60.0);
+        assert!((sequencer.frame_at(0.42).pitch - 62.0).abs() < 1e-9);
+        assert_eq!(sequencer.frame_at(0.45).pitch, 64.0);
+
+        assert_eq!(sequencer.frame_at(0.85).gesture.voicing, 0.0);
+        assert_eq!(
+            sequencer.frame_at(2.0).articulation,
+            Vowel::I.articulation()
+        );
+    }
+
+    #[test]
+    fn test_render_block() {
+        let mut sequencer = Sequencer::new(vec![
+            PhonemeEvent::new(Phoneme::Vowel(Vowel::A), 0.1, 57.0),
+            PhonemeEvent::new(
+                Phoneme::Consonant(Consonant::from_ipa("l").unwrap()),
+                0.05,
+                59.0,
+            ),
+            PhonemeEvent::new(Phoneme::Vowel(Vowel::U), 0.1, 60.0),
+        ]);
+        let mut voice: Voice<f32> = Voice::new(44100.0);
+        let mut output = vec![0.0; 4410];
+
+        sequencer.render_block(&mut voice, &mut output);
+        assert!(voice.is_held());
+        assert!((voice.frequency() - 220.0).abs() < 1e-3);
+        assert!(output.iter().all(|sample| sample.is_finite()));
+        assert!(output.iter().any(|&sample| sample != 0.0));
+
+        // pitch glides into the consonant
+        sequencer.render_block(&mut voice, &mut [0.0; 1764]);
+        assert!((voice.frequency() - 246.94).abs() < 1e-2);
+
+        for _ in 0..10 {
+            sequencer.render_block(&mut voice, &mut output);
+        }
+        assert!(sequencer.is_finished(44100.0));
+        assert!(!voice.is_held());
+    }
+}
@@ -1,3 +1,6 @@
+pub mod noise;
+pub mod queue;
+
 /// Convert the midi note's pitch into the equivalent frequency.
 ///
 /// This function assumes A4 is 440hz.
@@ -0,0 +1,172 @@
+//! A bounded, lock-free single-producer single-consumer queue.
+//!
+//! Neither end locks or allocates once the queue is created, so one end may be used on a
+//! real-time audio thread while the other is used elsewhere.
+
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// The storage shared by both ends of a queue.
+struct Shared<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    /// The number of values ever popped (only written by the consumer).
+    head: AtomicUsize,
+    /// The number of values ever pushed (only written by the producer).
+    tail: AtomicUsize,
+}
+
+// Each slot is accessed by one end at a time, as handed over through `head` and `tail`.
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        let (head, tail) = (*self.head.get_mut(), *self.tail.get_mut());
+        let capacity = self.slots.len();
+        for offset in 0..tail.wrapping_sub(head) {
+            let slot = &mut self.slots[head.wrapping_add(offset) % capacity];
+            unsafe { slot.get_mut().assume_init_drop() };
+        }
+    }
+}
+
+/// The sending end of a queue.
+pub struct Producer<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// The receiving end of a queue.
+pub struct Consumer<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// Creates a queue holding at most `capacity` values (at least 1).
+pub fn channel<T: Send>(capacity: usize) -> (Producer<T>, Consumer<T>) {
+    let slots = (0..capacity.max(1))
+        .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+        .collect();
+    let shared = Arc::new(Shared {
+        slots,
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+    });
+    (
+        Producer {
+            shared: Arc::clone(&shared),
+        },
+        Consumer { shared },
+    )
+}
+
+impl<T: Send> Producer<T> {
+    /// Pushes a value onto the queue, returning it if the queue is full.
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        let shared = &*self.shared;
+        let tail = shared.tail.load(Ordering::Relaxed);
+        let head = shared.head.load(Ordering::Acquire);
+        let capacity = shared.slots.len();
+        if tail.wrapping_sub(head) == capacity {
+            return Err(value);
+        }
+
+        unsafe { (*shared.slots[tail % capacity].get()).write(value) };
+        shared.tail.store(tail.wrapping_add(1), Ordering::Release);
+        Ok(())
+    }
+
+    /// Returns the maximum number of values the queue holds.
+    pub fn capacity(&self) -> usize {
+        self.shared.slots.len()
+    }
+}
+
+impl<T: Send> Consumer<T> {
+    /// Pops the oldest value from the queue, if any.
+    pub fn pop(&mut self) -> Option<T> {
+        let shared = &*self.shared;
+        let head = shared.head.load(Ordering::Relaxed);
+        let tail = shared.tail.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+
+        let capacity = shared.slots.len();
+        let value = unsafe { (*shared.slots[head % capacity].get()).assume_init_read() };
+        shared.head.store(head.wrapping_add(1), Ordering::Release);
+        Some(value)
+    }
+
+    /// Returns an iterator popping every value currently in the queue.
+    pub fn drain(&mut self) -> impl Iterator<Item = T> + '_ {
+        std::iter::from_fn(move || self.pop())
+    }
+
+    /// Returns the number of values in the queue.
+    pub fn len(&self) -> usize {
+        let tail = self.shared.tail.load(Ordering::Acquire);
+        tail.wrapping_sub(self.shared.head.load(Ordering::Relaxed))
+    }
+
+    /// Returns whether the queue is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_push_pop() {
+        let (mut producer, mut consumer) = channel(2);
+        assert_eq!(consumer.pop(), None);
+        assert_eq!(producer.push(1), Ok(()));
+        assert_eq!(producer.push(2), Ok(()));
+        assert_eq!(producer.push(3), Err(3));
+        assert_eq!(consumer.len(), 2);
+        assert_eq!(consumer.pop(), Some(1));
+        assert_eq!(producer.push(3), Ok(()));
+        assert_eq!(consumer.drain().collect::<Vec<_>>(), vec![2, 3]);
+        assert!(consumer.is_empty());
+    }
+
+    #[test]
+    fn test_across_threads() {
+        let (mut producer, mut consumer) = channel(16);
+        let sender = thread::spawn(move || {
+            for i in 0..10_000 {
+                let mut value = i;
+                while let Err(rejected) = producer.push(value) {
+                    value = rejected;
+                    thread::yield_now();
+                }
+            }
+        });
+
+        let mut expected = 0;
+        while expected < 10_000 {
+            match consumer.pop() {
+                Some(value) => {
+                    assert_eq!(value, expected);
+                    expected += 1;
+                }
+                None => thread::yield_now(),
+            }
+        }
+        sender.join().unwrap();
+    }
+
+    #[test]
+    fn test_drops_remaining_values() {
+        let value = Arc::new(());
+        let (mut producer, consumer) = channel(4);
+        producer.push(Arc::clone(&value)).unwrap();
+        producer.push(Arc::clone(&value)).unwrap();
+        drop(producer);
+        drop(consumer);
+        assert_eq!(Arc::strong_count(&value), 1);
+    }
+}
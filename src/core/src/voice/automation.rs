@@ -0,0 +1,258 @@
+//! Time-stamped automation of voice parameters, for offline rendering.
+
+use super::Parameter;
+use crate::filter::stateful::envelope::Curve;
+
+/// How a track moves from a keyframe to the next.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Interpolation {
+    /// Hold the keyframe's value until the next keyframe.
+    Step,
+    /// Move along a curve to the next keyframe's value.
+    Curve(Curve),
+}
+
+impl Interpolation {
+    /// Linear interpolation to the next keyframe.
+    pub const LINEAR: Interpolation = Interpolation::Curve(Curve::Linear);
+}
+
+/// A parameter value at a point in time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Keyframe {
+    /// The time of the keyframe in seconds.
+    pub time: f64,
+    pub value: f64,
+    /// How the track moves from this keyframe to the next.
+    pub interpolation: Interpolation,
+}
+
+impl Keyframe {
+    pub fn new(time: f64, value: f64, interpolation: Interpolation) -> Keyframe {
+        Keyframe {
+            time,
+            value,
+            interpolation,
+        }
+    }
+}
+
+/// The keyframes of a single parameter, ordered by time.
+///
+/// Before the first keyframe the track holds the first value, and after the last keyframe it
+/// holds the last value. Deserialized keyframes are ordered as if inserted one by one.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "AutomationTrackData"))]
+pub struct AutomationTrack {
+    pub parameter: Parameter,
+    keyframes: Vec<Keyframe>,
+}
+
+/// The serialized form of an [`AutomationTrack`], whose keyframes may be in any order.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct AutomationTrackData {
+    parameter: Parameter,
+    keyframes: Vec<Keyframe>,
+}
+
+#[cfg(feature = "serde")]
+impl From<AutomationTrackData> for AutomationTrack {
+    fn from(data: AutomationTrackData) -> AutomationTrack {
+        AutomationTrack::with_keyframes(data.parameter, data.keyframes)
+    }
+}
+
+impl AutomationTrack {
+    /// Creates a track without keyframes.
+    pub fn new(parameter: Parameter) -> AutomationTrack {
+        AutomationTrack {
+            parameter,
+            keyframes: Vec::new(),
+        }
+    }
+
+    /// Creates a track from keyframes in any order, inserting each in turn so that later
+    /// keyframes replace earlier ones at the same time.
+    pub fn with_keyframes(parameter: Parameter, keyframes: Vec<Keyframe>) -> AutomationTrack {
+        let mut track = AutomationTrack::new(parameter);
+        for keyframe in keyframes {
+            track.insert(keyframe);
+        }
+        track
+    }
+
+    /// Inserts a keyframe in time order, replacing any keyframe at the same time.
+    pub fn insert(&mut self, keyframe: Keyframe) {
+        match self
+            .keyframes
+            .binary_search_by(|other| other.time.total_cmp(&keyframe.time))
+        {
+            Ok(index) => self.keyframes[index] = keyframe,
+            Err(index) => self.keyframes.insert(index, keyframe),
+        }
+    }
+
+    /// Removes every keyframe within a time range (in seconds, inclusive).
+    pub fn remove_range(&mut self, start: f64, end: f64) {
+        self.keyframes
+            .retain(|keyframe| keyframe.time < start || keyframe.time > end);
+    }
+
+    /// Returns the keyframes in time order.
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    /// Returns the value of the track at a time in seconds, if it has any keyframes.
+    pub fn value_at(&self, time: f64) -> Option<f64> {
+        let next = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time <= time);
+        if next == 0 {
+            return self.keyframes.first().map(|keyframe| keyframe.value);
+        }
+
+        let from = self.keyframes[next - 1];
+        let to = match self.keyframes.get(next) {
+            Some(to) => to,
+            None => return Some(from.value),
+        };
+        match from.interpolation {
+            Interpolation::Step => Some(from.value),
+            Interpolation::Curve(curve) => {
+                let x = (time - from.time) / (to.time - from.time);
+                Some(from.value + (to.value - from.value) * curve.shape(x))
+            }
+        }
+    }
+
+    /// Returns the time of the first keyframe after a time in seconds.
+    pub fn next_keyframe(&self, time: f64) -> Option<f64> {
+        let next = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time <= time);
+        self.keyframes.get(next).map(|keyframe| keyframe.time)
+    }
+}
+
+/// A set of automation tracks, at most one for each parameter.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Automation {
+    tracks: Vec<AutomationTrack>,
+}
+
+impl Automation {
+    /// Creates automation without tracks.
+    pub fn new() -> Automation {
+        Automation::default()
+    }
+
+    /// Returns the track of a parameter, if any.
+    pub fn track(&self, parameter: Parameter) -> Option<&AutomationTrack> {
+        self.tracks
+            .iter()
+            .find(|track| track.parameter == parameter)
+    }
+
+    /// Returns the track of a parameter, creating an empty one if there is none.
+    pub fn track_mut(&mut self, parameter: Parameter) -> &mut AutomationTrack {
+        match self
+            .tracks
+            .iter()
+            .position(|track| track.parameter == parameter)
+        {
+            Some(index) => &mut self.tracks[index],
+            None => {
+                self.tracks.push(AutomationTrack::new(parameter));
+                self.tracks.last_mut().unwrap()
+            }
+        }
+    }
+
+    /// Inserts a keyframe into the track of a parameter.
+    pub fn insert(&mut self, parameter: Parameter, keyframe: Keyframe) {
+        self.track_mut(parameter).insert(keyframe);
+    }
+
+    /// Returns every track.
+    pub fn tracks(&self) -> &[AutomationTrack] {
+        &self.tracks
+    }
+
+    /// Returns the time of the first keyframe of any track after a time in seconds.
+    pub fn next_keyframe(&self, time: f64) -> Option<f64> {
+        self.tracks
+            .iter()
+            .filter_map(|track| track.next_keyframe(time))
+            .min_by(f64::total_cmp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_value_at() {
+        let mut track = AutomationTrack::new(Parameter::Tenseness);
+        assert_eq!(track.value_at(0.0), None);
+
+        track.insert(Keyframe::new(1.0, 0.2, Interpolation::LINEAR));
+        track.insert(Keyframe::new(3.0, 0.8, Interpolation::Step));
+        track.insert(Keyframe::new(2.0, 0.6, Interpolation::Step));
+        track.insert(Keyframe::new(1.0, 0.4, Interpolation::LINEAR));
+        assert_eq!(track.keyframes().len(), 3);
+
+        assert_eq!(track.value_at(0.0), Some(0.4));
+        assert!((track.value_at(1.5).unwrap() - 0.5).abs() < 1e-9);
+        assert_eq!(track.value_at(2.0), Some(0.6));
+        assert_eq!(track.value_at(2.999), Some(0.6));
+        assert_eq!(track.value_at(10.0), Some(0.8));
+        assert_eq!(track.next_keyframe(1.0), Some(2.0));
+        assert_eq!(track.next_keyframe(3.0), None);
+
+        track.remove_range(1.5, 2.5);
+        assert!((track.value_at(2.0).unwrap() - 0.6).abs() < 1e-9);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_with_keyframes() {
+        let track = AutomationTrack::with_keyframes(
+            Parameter::Tenseness,
+            vec![
+                Keyframe::new(2.0, 0.8, Interpolation::Step),
+                Keyframe::new(0.0, 0.2, Interpolation::Step),
+                Keyframe::new(2.0, 0.6, Interpolation::Step),
+            ],
+        );
+        let times: Vec<f64> = track.keyframes().iter().map(|k| k.time).collect();
+        assert_eq!(times, [0.0, 2.0]);
+        assert_eq!(track.value_at(1.0), Some(0.2));
+        assert_eq!(track.value_at(3.0), Some(0.6));
+        assert_eq!(track.next_keyframe(0.0), Some(2.0));
+    }
+
+    #[test]
+    fn test_next_keyframe() {
+        let mut automation = Automation::new();
+        automation.insert(
+            Parameter::Pitch,
+            Keyframe::new(2.0, 60.0, Interpolation::Step),
+        );
+        automation.insert(
+            Parameter::Velum,
+            Keyframe::new(0.5, 1.0, Interpolation::LINEAR),
+        );
+        assert_eq!(automation.tracks().len(), 2);
+        assert_eq!(automation.next_keyframe(0.0), Some(0.5));
+        assert_eq!(automation.next_keyframe(0.5), Some(2.0));
+        assert!(automation.track(Parameter::Tenseness).is_none());
+    }
+}
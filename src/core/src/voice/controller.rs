@@ -0,0 +1,155 @@
+//! Real-time safe control of voices from other threads.
+
+use super::{Parameter, Voice, VoicePool};
+use crate::filter::stateful::tract::consonants::Constriction;
+use crate::filter::stateful::tract::vowels::Vowel;
+use crate::float::Float;
+use crate::utils::queue::{channel, Consumer, Producer};
+
+/// A change to a voice, sent from a control thread to the audio thread.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    NoteOn {
+        pitch: u8,
+        velocity: u8,
+    },
+    NoteOff {
+        pitch: u8,
+    },
+    /// Pitch bend in semitones.
+    PitchBend(f64),
+    /// A new value (not an offset) for a parameter.
+    Parameter(Parameter, f64),
+    Vowel(Vowel),
+    Constriction(Option<Constriction>),
+    /// A midi continuous controller's value (0-127) for modulation.
+    Controller {
+        controller: u8,
+        value: u8,
+    },
+    /// Silence every voice immediately.
+    Reset,
+}
+
+/// A handle for sending commands to a voice, which may be moved to another thread.
+///
+/// Sending never blocks: if the queue is full the command is returned to the caller.
+pub struct VoiceController {
+    producer: Producer<Command>,
+}
+
+/// The audio thread's end of a [`VoiceController`]'s queue.
+///
+/// Draining the queue neither locks nor allocates.
+pub struct CommandReceiver {
+    consumer: Consumer<Command>,
+}
+
+/// Creates a controller and the receiver of its commands, queuing at most `capacity` commands.
+pub fn command_queue(capacity: usize) -> (VoiceController, CommandReceiver) {
+    let (producer, consumer) = channel(capacity);
+    (VoiceController { producer }, CommandReceiver { consumer })
+}
+
+impl VoiceController {
+    /// Sends a command, returning it if the queue is full.
+    pub fn send(&mut self, command: Command) -> Result<(), Command> {
+        self.producer.push(command)
+    }
+
+    pub fn note_on(&mut self, pitch: u8, velocity: u8) -> Result<(), Command> {
+        self.send(Command::NoteOn { pitch, velocity })
+    }
+
+    pub fn note_off(&mut self, pitch: u8) -> Result<(), Command> {
+        self.send(Command::NoteOff { pitch })
+    }
+
+    pub fn set_pitch_bend(&mut self, semitones: f64) -> Result<(), Command> {
+        self.send(Command::PitchBend(semitones))
+    }
+
+    pub fn set_parameter(&mut self, parameter: Parameter, value: f64) -> Result<(), Command> {
+        self.send(Command::Parameter(parameter, value))
+    }
+
+    pub fn set_vowel(&mut self, vowel: Vowel) -> Result<(), Command> {
+        self.send(Command::Vowel(vowel))
+    }
+}
+
+impl CommandReceiver {
+    /// Applies every queued command to a voice.
+    pub fn apply_to_voice<T: Float>(&mut self, voice: &mut Voice<T>) {
+        for command in self.consumer.drain() {
+            voice.apply(command);
+        }
+    }
+
+    /// Applies every queued command to a pool of voices.
+    pub fn apply_to_pool<T: Float>(&mut self, pool: &mut VoicePool<T>) {
+        for command in self.consumer.drain() {
+            pool.apply(command);
+        }
+    }
+
+    /// Pops the oldest queued command, if any.
+    pub fn pop(&mut self) -> Option<Command> {
+        self.consumer.pop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice::PolyphonyMode;
+    use std::thread;
+
+    #[test]
+    fn test_control_from_another_thread() {
+        let (mut controller, mut receiver) = command_queue(8);
+        thread::spawn(move || {
+            controller.note_on(57, 100).unwrap();
+            controller.set_pitch_bend(12.0).unwrap();
+            controller.set_parameter(Parameter::Tenseness, 0.9).unwrap();
+        })
+        .join()
+        .unwrap();
+
+        let mut voice: Voice<f32> = Voice::new(44100.0);
+        receiver.apply_to_voice(&mut voice);
+        assert_eq!(receiver.pop(), None);
+        assert!(voice.is_held());
+        assert!((voice.frequency() - 440.0).abs() < 1e-3);
+        assert!((voice.glottis().tenseness - 0.9).abs() < 1e-9);
+
+        let (mut controller, mut receiver) = command_queue(1);
+        controller.note_off(57).unwrap();
+        assert!(controller.note_off(57).is_err());
+        receiver.apply_to_voice(&mut voice);
+        assert!(!voice.is_held());
+    }
+
+    #[test]
+    fn test_control_mono_pool() {
+        let (mut controller, mut receiver) = command_queue(8);
+        let mut pool: VoicePool<f32> = VoicePool::new(4, 44100.0);
+        pool.set_mode(PolyphonyMode::Mono);
+
+        controller.note_on(60, 100).unwrap();
+        controller.note_on(64, 100).unwrap();
+        receiver.apply_to_pool(&mut pool);
+        assert_eq!(pool.voices()[0].note(), Some(64));
+        assert_eq!(pool.active_voices(), 1);
+
+        // releasing the sounding note returns to the one still held
+        controller.note_off(64).unwrap();
+        receiver.apply_to_pool(&mut pool);
+        assert_eq!(pool.voices()[0].note(), Some(60));
+        assert!(pool.voices()[0].is_held());
+
+        controller.note_off(60).unwrap();
+        receiver.apply_to_pool(&mut pool);
+        assert!(!pool.voices()[0].is_held());
+    }
+}
@@ -0,0 +1,337 @@
+//! A choir section of slightly different singers, mixed to stereo.
+
+use super::Voice;
+use crate::filter::stateful::tract::error::TractError;
+use crate::filter::stateful::tract::{TractBuilder, BLOCK_SIZE};
+use crate::float::Float;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f64::consts::FRAC_PI_4;
+
+/// The number of oral sections of an average singer's tract.
+const ORAL_LENGTH: f64 = 44.0;
+/// The number of nasal sections of an average singer's tract.
+const NASAL_LENGTH: f64 = 28.0;
+/// The oral section of an average singer's tract at which the nasal cavity branches.
+const VELUM_INDEX: f64 = 17.0;
+/// The vibrato rate of an average singer, in Hz.
+const VIBRATO_RATE: f64 = 6.0;
+/// The vibrato depth of every singer, in Hz.
+const VIBRATO_DEPTH: f64 = 0.1;
+/// The tenseness of an average singer.
+const TENSENESS: f64 = 0.5;
+
+/// The range over which each singer of an [`Ensemble`] varies from the average singer.
+///
+/// Each singer draws a uniformly distributed offset within ± each range.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Variation {
+    /// Variation of tract length, as a fraction of the average length.
+    pub tract_length: f64,
+    /// Variation of pitch, in cents.
+    pub detune: f64,
+    /// Variation of vibrato rate, in Hz.
+    pub vibrato_rate: f64,
+    /// Maximum delay of each singer's onset, in seconds.
+    pub onset: f64,
+    /// Variation of glottal tenseness.
+    pub tenseness: f64,
+}
+
+impl Default for Variation {
+    fn default() -> Variation {
+        Variation {
+            tract_length: 0.08,
+            detune: 12.0,
+            vibrato_rate: 0.8,
+            onset: 0.04,
+            tenseness: 0.1,
+        }
+    }
+}
+
+/// A singer within an ensemble.
+#[derive(Clone, Debug)]
+struct Singer<T: Float> {
+    voice: Voice<T>,
+    /// The singer's pitch offset in semitones.
+    detune: f64,
+    /// The singer's onset delay in samples.
+    delay: usize,
+    /// A note waiting for the singer's onset delay, with the samples remaining.
+    pending: Option<(u8, u8, usize)>,
+    /// Gains of the singer in the left and right channels.
+    gains: (T, T),
+}
+
+/// An ensemble of singers sharing the same notes, each varying slightly in voice and timing.
+///
+/// Singers are panned evenly across the stereo field and the mix is scaled by the inverse
+/// square root of the number of singers, keeping its level roughly independent of size. Onset
+/// delays are applied with a resolution of [`BLOCK_SIZE`] samples.
+#[derive(Clone, Debug)]
+pub struct Ensemble<T: Float = f64> {
+    singers: Vec<Singer<T>>,
+    /// The stereo width in range [0-1].
+    spread: f64,
+    /// The pitch bend of every singer in semitones.
+    bend: f64,
+    /// The output of a single singer for the current block.
+    scratch: [T; BLOCK_SIZE],
+}
+
+impl<T: Float> Ensemble<T> {
+    /// Creates an ensemble of `size` singers with the default variation, seeded for
+    /// reproducibility.
+    ///
+    /// Returns an error if the sample rate is invalid.
+    pub fn new(size: usize, sample_rate: f64, seed: u64) -> Result<Ensemble<T>, TractError> {
+        Ensemble::with_variation(size, sample_rate, seed, &Variation::default())
+    }
+
+    /// Creates an ensemble of `size` singers varying within `variation`, seeded for
+    /// reproducibility.
+    ///
+    /// Returns an error if the sample rate is invalid or a singer's tract would be too short.
+    pub fn with_variation(
+        size: usize,
+        sample_rate: f64,
+        seed: u64,
+        variation: &Variation,
+    ) -> Result<Ensemble<T>, TractError> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let offset = |rng: &mut StdRng, range: f64| {
+            if range > 0.0 {
+                rng.gen_range(-range..=range)
+            } else {
+                0.0
+            }
+        };
+
+        let singers = (0..size.max(1))
+            .map(|_| {
+                let scale = 1.0 + offset(&mut rng, variation.tract_length);
+                let tract = TractBuilder::new()
+                    .oral_length((ORAL_LENGTH * scale).round() as usize)
+                    .nasal_length((NASAL_LENGTH * scale).round() as usize)
+                    .velum_index((VELUM_INDEX * scale).round() as usize)
+                    .sample_rate(sample_rate)
+                    .build()?;
+
+                let mut voice = Voice::with_tract(tract, sample_rate);
+                voice.set_seed(rng.gen());
+                let vibrato_rate = VIBRATO_RATE + offset(&mut rng, variation.vibrato_rate);
+                voice.glottis_mut().set_vibrato(vibrato_rate, VIBRATO_DEPTH);
+                voice.set_tenseness(TENSENESS + offset(&mut rng, variation.tenseness));
+                let detune = offset(&mut rng, variation.detune) / 100.0;
+                voice.set_pitch_bend(detune);
+                let delay = offset(&mut rng, variation.onset).abs() * sample_rate;
+
+                Ok(Singer {
+                    voice,
+                    detune,
+                    delay: delay as usize,
+                    pending: None,
+                    gains: (T::zero(), T::zero()),
+                })
+            })
+            .collect::<Result<_, TractError>>()?;
+
+        let mut ensemble = Ensemble {
+            singers,
+            spread: 1.0,
+            bend: 0.0,
+            scratch: [T::zero(); BLOCK_SIZE],
+        };
+        ensemble.set_spread(1.0);
+        Ok(ensemble)
+    }
+
+    /// Returns the number of singers.
+    pub fn size(&self) -> usize {
+        self.singers.len()
+    }
+
+    /// Sets the stereo width in range [0-1] (0 being mono).
+    pub fn set_spread(&mut self, spread: f64) {
+        self.spread = spread.clamp(0.0, 1.0);
+        let size = self.singers.len();
+        let level = 1.0 / (size as f64).sqrt();
+        for (i, singer) in self.singers.iter_mut().enumerate() {
+            let position = if size > 1 {
+                self.spread * (2.0 * i as f64 / (size - 1) as f64 - 1.0)
+            } else {
+                0.0
+            };
+            // equal-power panning
+            let angle = (position + 1.0) * FRAC_PI_4;
+            singer.gains = (
+                T::from_f64(angle.cos() * level),
+                T::from_f64(angle.sin() * level),
+            );
+        }
+    }
+
+    /// Returns the stereo width in range [0-1].
+    pub fn spread(&self) -> f64 {
+        self.spread
+    }
+
+    /// Starts every singer singing a note, each after their onset delay.
+    pub fn note_on(&mut self, pitch: u8, velocity: u8) {
+        for singer in &mut self.singers {
+            singer.pending = Some((pitch, velocity, singer.delay));
+        }
+    }
+
+    /// Releases the note of every singer (including any yet to start).
+    pub fn note_off(&mut self) {
+        for singer in &mut self.singers {
+            singer.pending = None;
+            singer.voice.note_off();
+        }
+    }
+
+    /// Bends the pitch of every singer by a number of semitones.
+    pub fn set_pitch_bend(&mut self, semitones: f64) {
+        self.bend = semitones;
+        for singer in &mut self.singers {
+            singer.voice.set_pitch_bend(semitones + singer.detune);
+        }
+    }
+
+    /// Sets the dynamics of every singer in range [0-1].
+    pub fn set_dynamics(&mut self, dynamics: f64) {
+        for singer in &mut self.singers {
+            singer.voice.set_dynamics(dynamics);
+        }
+    }
+
+    /// Returns whether any singer is producing sound or about to start.
+    pub fn is_active(&self) -> bool {
+        self.singers
+            .iter()
+            .any(|singer| singer.pending.is_some() || singer.voice.is_active())
+    }
+
+    /// Returns an iterator over the singers' voices for direct control (such as articulation).
+    ///
+    /// Pitch bend should be set through [`Ensemble::set_pitch_bend`] to preserve each singer's
+    /// detuning.
+    pub fn voices_mut(&mut self) -> impl Iterator<Item = &mut Voice<T>> {
+        self.singers.iter_mut().map(|singer| &mut singer.voice)
+    }
+
+    /// Renders the stereo mix of every singer, overwriting `left` and `right`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `left` and `right` differ in length.
+    pub fn render_block(&mut self, left: &mut [T], right: &mut [T]) {
+        assert_eq!(left.len(), right.len(), "channels differ in length");
+        left.iter_mut().for_each(|sample| *sample = T::zero());
+        right.iter_mut().for_each(|sample| *sample = T::zero());
+
+        let chunks = left
+            .chunks_mut(BLOCK_SIZE)
+            .zip(right.chunks_mut(BLOCK_SIZE));
+        for (left, right) in chunks {
+            let length = left.len();
+            let scratch = &mut self.scratch[..length];
+            for singer in &mut self.singers {
+                if let Some((pitch, velocity, delay)) = singer.pending {
+                    if delay < length {
+                        singer.voice.note_on(pitch, velocity);
+                        singer.pending = None;
+                    } else {
+                        singer.pending = Some((pitch, velocity, delay - length));
+                    }
+                }
+                if !singer.voice.is_active() {
+                    continue;
+                }
+
+                singer.voice.render_block(scratch);
+                let (gain_left, gain_right) = singer.gains;
+                let channels = left.iter_mut().zip(right.iter_mut()).zip(scratch.iter());
+                for ((left, right), &sample) in channels {
+                    *left += sample * gain_left;
+                    *right += sample * gain_right;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(ensemble: &mut Ensemble<f32>, length: usize) -> (Vec<f32>, Vec<f32>) {
+        let (mut left, mut right) = (vec![0.0; length], vec![0.0; length]);
+        ensemble.render_block(&mut left, &mut right);
+        (left, right)
+    }
+
+    #[test]
+    fn test_singers_vary() {
+        let mut ensemble: Ensemble<f32> = Ensemble::new(8, 44100.0, 7).unwrap();
+        let frequencies: Vec<f64> = ensemble
+            .singers
+            .iter()
+            .map(|singer| singer.voice.frequency())
+            .collect();
+        assert!(frequencies.windows(2).any(|pair| pair[0] != pair[1]));
+        assert!(ensemble
+            .singers
+            .iter()
+            .all(|singer| singer.delay <= (0.04 * 44100.0) as usize));
+
+        // the same seed produces the same singers
+        let mut same: Ensemble<f32> = Ensemble::new(8, 44100.0, 7).unwrap();
+        for (a, b) in ensemble.singers.iter().zip(&same.singers) {
+            assert_eq!(a.detune, b.detune);
+            assert_eq!(a.delay, b.delay);
+        }
+        // and renders the same output, noise included
+        ensemble.note_on(57, 100);
+        same.note_on(57, 100);
+        assert_eq!(render(&mut ensemble, 4410), render(&mut same, 4410));
+    }
+
+    #[test]
+    fn test_reject_invalid_configuration() {
+        assert!(Ensemble::<f32>::new(4, -44100.0, 1).is_err());
+        // some singers' tracts would shrink below the shortest valid tract
+        let variation = Variation {
+            tract_length: 0.99,
+            ..Variation::default()
+        };
+        assert!(matches!(
+            Ensemble::<f32>::with_variation(64, 44100.0, 1, &variation),
+            Err(TractError::InvalidLength { .. })
+        ));
+    }
+
+    #[test]
+    fn test_stereo_spread() {
+        let mut ensemble: Ensemble<f32> = Ensemble::new(4, 44100.0, 1).unwrap();
+        ensemble.note_on(57, 100);
+        assert!(ensemble.is_active());
+        let (left, right) = render(&mut ensemble, 8820);
+        assert!(left.iter().chain(&right).all(|x| x.is_finite()));
+        assert!(left.iter().zip(&right).any(|(l, r)| (l - r).abs() > 1e-4));
+
+        // without spread both channels are identical
+        ensemble.set_spread(0.0);
+        let (left, right) = render(&mut ensemble, 4410);
+        for (l, r) in left.iter().zip(&right) {
+            assert!((l - r).abs() < 1e-6);
+        }
+
+        ensemble.note_off();
+        render(&mut ensemble, 44100);
+        assert!(!ensemble.is_active());
+    }
+}
@@ -0,0 +1,553 @@
+//! A complete singing voice, combining a glottal source with a vocal tract.
+
+pub mod automation;
+pub mod controller;
+pub mod ensemble;
+pub mod modulation;
+pub mod pool;
+
+use crate::filter::stateful::envelope::{Adsr, Curve, Envelope};
+use crate::filter::stateful::tract::consonants::{Constriction, GestureFrame};
+use crate::filter::stateful::tract::vowels::{Articulation, Vowel};
+use crate::filter::stateful::tract::{Tract, TractBuilder, BLOCK_SIZE};
+use crate::float::Float;
+use crate::source::Glottis;
+use crate::utils::midi_pitch_to_freq;
+use crate::utils::noise::WhiteNoise;
+
+pub use automation::{Automation, AutomationTrack, Interpolation, Keyframe};
+pub use controller::{command_queue, Command, CommandReceiver, VoiceController};
+pub use ensemble::{Ensemble, Variation};
+pub use modulation::{ModulationMatrix, Parameter, Polarity, Route, Source};
+pub use pool::{PolyphonyMode, StealPolicy, VoicePool};
+
+/// The default number of sections in the oral cavity.
+const ORAL_LENGTH: usize = 44;
+/// The default number of sections in the nasal cavity.
+const NASAL_LENGTH: usize = 28;
+/// The default envelope of the voice's intensity.
+const ENVELOPE: Adsr = Adsr {
+    attack: 0.08,
+    decay: 0.0,
+    sustain: 1.0,
+    release: 0.2,
+    attack_curve: Curve::Linear,
+    release_curve: Curve::Linear,
+};
+
+/// A monophonic singing voice.
+///
+/// Owns a [`Glottis`] and a [`Tract`] along with the sample rate and clock they share, and
+/// renders audio in blocks of precision `T`.
+#[derive(Clone, Debug)]
+pub struct Voice<T: Float = f64> {
+    glottis: Glottis<T>,
+    tract: Tract<T>,
+    sample_rate: f64,
+    /// The number of samples rendered.
+    clock: u64,
+    /// The midi pitch of the current note, if any.
+    note: Option<u8>,
+    /// The envelope of the voice's intensity, held while the current note is held.
+    envelope: Envelope,
+    /// The velocity of the current note in range [0-1].
+    velocity: f64,
+    /// The dynamics of the voice in range [0-1].
+    dynamics: f64,
+    /// The voicing of the current consonant gesture in range [0-1].
+    voicing: f64,
+    /// The aspiration of the current consonant gesture in range [0-1].
+    aspiration: f64,
+    /// The frequency of the current note in Hz, before pitch bend.
+    frequency: f64,
+    /// The pitch bend in semitones.
+    bend: f64,
+    /// Modulation sources and their routes to the voice's parameters.
+    modulation: ModulationMatrix,
+    /// The offset of pitch from modulation in semitones.
+    pitch_offset: f64,
+    /// The tenseness of the glottis before modulation.
+    tenseness: f64,
+    /// The opening of the velum before modulation.
+    velum: f64,
+    /// The articulation of the tract before modulation, if set.
+    articulation: Option<Articulation>,
+    /// Automation of the voice's parameters, timed by the voice's clock.
+    automation: Option<Automation>,
+    /// The glottal opening at each sample of the current block.
+    opening: [f64; BLOCK_SIZE],
+    /// Turbulence noise for each sample of the current block.
+    noise: [T; BLOCK_SIZE],
+    /// The source of turbulence noise within the tract.
+    turbulence: WhiteNoise,
+}
+
+impl<T: Float> Voice<T> {
+    /// Creates a silent voice with a tract of the default size.
+    pub fn new(sample_rate: f64) -> Voice<T> {
+        let tract = TractBuilder::new()
+            .oral_length(ORAL_LENGTH)
+            .nasal_length(NASAL_LENGTH)
+            .build()
+            .expect("invalid tract configuration");
+        Voice::with_tract(tract, sample_rate)
+    }
+
+    /// Creates a silent voice from an existing tract (see
+    /// [`TractBuilder`]).
+    pub fn with_tract(mut tract: Tract<T>, sample_rate: f64) -> Voice<T> {
+        tract.set_sample_rate(sample_rate);
+        tract.init();
+
+        let mut glottis = Glottis::new();
+        glottis.set_sample_rate(sample_rate);
+        glottis.set_intensity(0.0);
+        let tenseness = glottis.tenseness;
+
+        Voice {
+            glottis,
+            tract,
+            sample_rate,
+            clock: 0,
+            note: None,
+            envelope: ENVELOPE.envelope(sample_rate),
+            velocity: 0.0,
+            dynamics: 1.0,
+            voicing: 1.0,
+            aspiration: 0.0,
+            frequency: 440.0,
+            bend: 0.0,
+            modulation: ModulationMatrix::new(),
+            pitch_offset: 0.0,
+            tenseness,
+            velum: 0.0,
+            articulation: None,
+            automation: None,
+            opening: [0.0; BLOCK_SIZE],
+            noise: [T::zero(); BLOCK_SIZE],
+            turbulence: WhiteNoise::new(0),
+        }
+    }
+
+    /// Seeds the voice's noise, so voices with the same seed produce the same output.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.glottis.set_seed(seed);
+        self.turbulence = WhiteNoise::new(seed.wrapping_add(1));
+    }
+
+    /// Starts singing a note at a midi pitch and velocity.
+    pub fn note_on(&mut self, pitch: u8, velocity: u8) {
+        self.note = Some(pitch);
+        self.envelope.note_on();
+        self.velocity = f64::from(velocity.min(127)) / 127.0;
+        self.modulation.note_on(self.velocity);
+        self.frequency = f64::from(midi_pitch_to_freq(pitch));
+        self.update_frequency();
+    }
+
+    /// Moves the current note to a new midi pitch without starting a new onset.
+    pub fn legato(&mut self, pitch: u8) {
+        self.note = Some(pitch);
+        self.frequency = f64::from(midi_pitch_to_freq(pitch));
+        self.update_frequency();
+    }
+
+    /// Releases the current note, letting the voice fall silent.
+    pub fn note_off(&mut self) {
+        self.envelope.note_off();
+        self.modulation.note_off();
+    }
+
+    /// Returns the midi pitch of the current (or most recently released) note.
+    pub fn note(&self) -> Option<u8> {
+        self.note
+    }
+
+    /// Returns whether the current note is held.
+    pub fn is_held(&self) -> bool {
+        self.envelope.is_held()
+    }
+
+    /// Returns whether the voice is producing sound (held, or released but not yet silent).
+    pub fn is_active(&self) -> bool {
+        self.envelope.is_active()
+    }
+
+    /// Returns the current intensity of the voice in range [0-1].
+    pub fn intensity(&self) -> f64 {
+        self.glottis.intensity()
+    }
+
+    /// Sets the envelope of the voice's intensity (see [`Adsr::envelope`]).
+    pub fn set_envelope(&mut self, mut envelope: Envelope) {
+        envelope.set_sample_rate(self.sample_rate);
+        self.envelope = envelope;
+    }
+
+    /// Returns the envelope of the voice's intensity.
+    pub fn envelope_mut(&mut self) -> &mut Envelope {
+        &mut self.envelope
+    }
+
+    /// Sets the frequency of the voice in Hz, independent of any note.
+    pub fn set_frequency(&mut self, frequency: f64) {
+        self.frequency = frequency;
+        self.update_frequency();
+    }
+
+    /// Returns the frequency of the voice in Hz, including pitch bend.
+    pub fn frequency(&self) -> f64 {
+        self.glottis.frequency
+    }
+
+    /// Bends the pitch of the voice by a number of semitones.
+    pub fn set_pitch_bend(&mut self, semitones: f64) {
+        self.bend = semitones;
+        self.update_frequency();
+    }
+
+    /// Sets the dynamics of the voice in range [0-1], scaling the intensity of each note.
+    pub fn set_dynamics(&mut self, dynamics: f64) {
+        self.dynamics = dynamics.clamp(0.0, 1.0);
+    }
+
+    /// Sets the tenseness of the glottis in range [0-1] (0 being breathy, 1 being pressed).
+    pub fn set_tenseness(&mut self, tenseness: f64) {
+        self.tenseness = tenseness.clamp(0.0, 1.0);
+        self.glottis.tenseness = self.tenseness;
+    }
+
+    /// Sets the target vowel of the tract.
+    pub fn set_vowel(&mut self, vowel: Vowel) {
+        self.set_articulation(&vowel.articulation());
+    }
+
+    /// Sets the target articulation of the tract.
+    pub fn set_articulation(&mut self, articulation: &Articulation) {
+        self.articulation = Some(*articulation);
+        self.tract.set_articulation(articulation);
+    }
+
+    /// Sets the constriction of the tract (formed by consonants).
+    pub fn set_constriction(&mut self, constriction: Option<Constriction>) {
+        self.tract.set_constriction(constriction);
+    }
+
+    /// Sets the opening of the velum in range [0-1] (0 being closed).
+    pub fn set_velum_opening(&mut self, opening: f64) {
+        self.velum = opening;
+        self.tract.set_velum_opening(opening);
+    }
+
+    /// Applies a consonant gesture, including its voicing and aspiration, to the voice.
+    pub fn articulate(&mut self, frame: &GestureFrame) {
+        self.tract.articulate(frame);
+        self.velum = frame.velum;
+        self.voicing = frame.voicing.clamp(0.0, 1.0);
+        self.aspiration = frame.aspiration.clamp(0.0, 1.0);
+    }
+
+    /// Sets a parameter to a new value (see [`Parameter`] for units).
+    pub fn set_parameter(&mut self, parameter: Parameter, value: f64) {
+        match parameter {
+            Parameter::Pitch => {
+                self.frequency = 440.0 * ((value - 69.0) / 12.0).exp2();
+                self.update_frequency();
+            }
+            Parameter::Intensity => self.set_dynamics(value),
+            Parameter::Tenseness => self.set_tenseness(value),
+            Parameter::Velum => self.set_velum_opening(value),
+            Parameter::TongueIndex | Parameter::TongueDiameter | Parameter::LipDiameter => {
+                let mut articulation = self
+                    .articulation
+                    .unwrap_or_else(|| Vowel::Schwa.articulation());
+                match parameter {
+                    Parameter::TongueIndex => articulation.tongue_index = value,
+                    Parameter::TongueDiameter => articulation.tongue_diameter = value,
+                    _ => articulation.lip_diameter = value,
+                }
+                self.set_articulation(&articulation);
+            }
+        }
+    }
+
+    /// Applies a command from a [`VoiceController`].
+    ///
+    /// A note off only releases the voice if it is singing that pitch.
+    pub fn apply(&mut self, command: Command) {
+        match command {
+            Command::NoteOn { pitch, velocity } => self.note_on(pitch, velocity),
+            Command::NoteOff { pitch } => {
+                if self.note == Some(pitch) {
+                    self.note_off();
+                }
+            }
+            Command::PitchBend(semitones) => self.set_pitch_bend(semitones),
+            Command::Parameter(parameter, value) => self.set_parameter(parameter, value),
+            Command::Vowel(vowel) => self.set_vowel(vowel),
+            Command::Constriction(constriction) => self.set_constriction(constriction),
+            Command::Controller { controller, value } => self.set_controller(controller, value),
+            Command::Reset => self.reset(),
+        }
+    }
+
+    /// Returns the voice's modulation sources and routes.
+    pub fn modulation_mut(&mut self) -> &mut ModulationMatrix {
+        &mut self.modulation
+    }
+
+    /// Sets a midi continuous controller's value (0-127) for modulation.
+    pub fn set_controller(&mut self, controller: u8, value: u8) {
+        self.modulation.set_controller(controller, value);
+    }
+
+    /// Sets the automation of the voice's parameters, timed from the start of the voice's clock.
+    pub fn set_automation(&mut self, automation: Option<Automation>) {
+        self.automation = automation;
+    }
+
+    /// Returns the automation of the voice's parameters.
+    pub fn automation(&self) -> Option<&Automation> {
+        self.automation.as_ref()
+    }
+
+    /// Returns the voice's glottis.
+    pub fn glottis(&self) -> &Glottis<T> {
+        &self.glottis
+    }
+
+    /// Returns the voice's glottis for direct control.
+    pub fn glottis_mut(&mut self) -> &mut Glottis<T> {
+        &mut self.glottis
+    }
+
+    /// Returns the voice's tract.
+    pub fn tract(&self) -> &Tract<T> {
+        &self.tract
+    }
+
+    /// Returns the voice's tract for direct control.
+    pub fn tract_mut(&mut self) -> &mut Tract<T> {
+        &mut self.tract
+    }
+
+    /// Returns the sample rate of the voice.
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// Returns the time of the voice's clock in seconds.
+    pub fn time(&self) -> f64 {
+        self.clock as f64 / self.sample_rate
+    }
+
+    /// Silences the voice, releasing its note and discarding all sound within the tract.
+    pub fn reset(&mut self) {
+        self.envelope.reset();
+        self.glottis.set_intensity(0.0);
+        self.tract.reset();
+    }
+
+    /// Renders a block of audio, overwriting `output`.
+    ///
+    /// Blocks are split at each keyframe so that automation steps land on the keyframe's sample.
+    /// Between keyframes, automation is evaluated at the last sample of each block of at most
+    /// [`BLOCK_SIZE`] samples, and the glottis and tract glide to the new values sample by sample
+    /// from those of the previous block. Linear segments are thus followed exactly, and curves
+    /// piecewise linearly.
+    pub fn render_block(&mut self, output: &mut [T]) {
+        let mut start = 0;
+        while start < output.len() {
+            let length = self.chunk_length(output.len() - start);
+            let chunk = &mut output[start..start + length];
+            start += length;
+
+            self.automate(length);
+            self.modulate(length);
+            for noise in &mut self.noise[..length] {
+                *noise = T::from_f64(self.turbulence.sample());
+            }
+
+            let opening = &mut self.opening[..length];
+            self.glottis.process_block_with_opening(chunk, opening);
+            self.tract
+                .process_block_with(chunk, Some(&self.noise[..length]), Some(opening));
+            self.clock += length as u64;
+        }
+    }
+
+    /// Returns the length of the next block, ending before the next keyframe and rendering a
+    /// keyframe's sample alone.
+    fn chunk_length(&self, remaining: usize) -> usize {
+        let length = remaining.min(BLOCK_SIZE);
+        let time = (self.clock as f64 - 0.5) / self.sample_rate;
+        let keyframe = match self.automation.as_ref().and_then(|a| a.next_keyframe(time)) {
+            Some(keyframe) => keyframe,
+            None => return length,
+        };
+
+        // tolerate rounding of keyframes that fall exactly on a sample
+        let sample = (keyframe * self.sample_rate - 1e-9).ceil().max(0.0) as u64;
+        match sample.saturating_sub(self.clock) as usize {
+            0 => 1,
+            until => length.min(until),
+        }
+    }
+
+    /// Applies the automation's values as of the end of a block of `length` samples.
+    fn automate(&mut self, length: usize) {
+        let automation = match self.automation.take() {
+            Some(automation) => automation,
+            None => return,
+        };
+
+        let time = (self.clock + length as u64 - 1) as f64 / self.sample_rate;
+        for track in automation.tracks() {
+            if let Some(value) = track.value_at(time) {
+                self.set_parameter(track.parameter, value);
+            }
+        }
+        self.automation = Some(automation);
+    }
+
+    /// Advances the intensity envelope and modulation over a block of `length` samples,
+    /// applying them to the glottis and tract.
+    ///
+    /// Parameters without a route are left as set, so direct control of the glottis and tract
+    /// is preserved.
+    fn modulate(&mut self, length: usize) {
+        let delta_time = length as f64 / self.sample_rate;
+        let level = self.envelope.advance(delta_time);
+        self.modulation.advance(delta_time);
+        let modulation = &self.modulation;
+
+        let scale = (1.0 + modulation.offset(Parameter::Intensity)).max(0.0);
+        let breath = level * self.velocity * self.dynamics * scale;
+        self.glottis.set_intensity(breath * self.voicing);
+        self.glottis.set_aspiration(breath * self.aspiration);
+
+        if modulation.is_routed(Parameter::Tenseness) {
+            let tenseness = self.tenseness + modulation.offset(Parameter::Tenseness);
+            self.glottis.tenseness = tenseness.clamp(0.0, 1.0);
+        }
+        if modulation.is_routed(Parameter::Velum) {
+            let velum = self.velum + modulation.offset(Parameter::Velum);
+            self.tract.set_velum_opening(velum);
+        }
+        if let Some(articulation) = self.articulation {
+            let routed = modulation
+                .routes()
+                .iter()
+                .any(|route| route.parameter.is_articulatory());
+            if routed {
+                let tongue_index =
+                    articulation.tongue_index + modulation.offset(Parameter::TongueIndex);
+                self.tract.set_articulation(&Articulation {
+                    tongue_index: tongue_index.clamp(0.0, 1.0),
+                    tongue_diameter: articulation.tongue_diameter
+                        + modulation.offset(Parameter::TongueDiameter),
+                    lip_diameter: (articulation.lip_diameter
+                        + modulation.offset(Parameter::LipDiameter))
+                    .max(0.0),
+                });
+            }
+        }
+        if modulation.is_routed(Parameter::Pitch) {
+            self.pitch_offset = modulation.offset(Parameter::Pitch);
+            self.update_frequency();
+        }
+    }
+
+    fn update_frequency(&mut self) {
+        let semitones = self.bend + self.pitch_offset;
+        self.glottis.frequency = self.frequency * (semitones / 12.0).exp2();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_note_lifecycle() {
+        let mut voice: Voice<f32> = Voice::new(44100.0);
+        assert!(!voice.is_active());
+
+        voice.note_on(57, 127);
+        assert!((voice.frequency() - 220.0).abs() < 1e-3);
+        voice.set_pitch_bend(12.0);
+        assert!((voice.frequency() - 440.0).abs() < 1e-3);
+
+        let mut buffer = [0.0; 4410];
+        voice.render_block(&mut buffer);
+        assert!(buffer.iter().all(|x| x.is_finite()));
+        assert!(buffer.iter().any(|x| x.abs() > 1e-3));
+        assert!((voice.time() - 0.1).abs() < 1e-9);
+
+        voice.note_off();
+        assert!(voice.is_active());
+        for _ in 0..10 {
+            voice.render_block(&mut buffer);
+        }
+        assert!(!voice.is_active());
+        assert_eq!(voice.note(), Some(57));
+    }
+
+    #[test]
+    fn test_modulation() {
+        use crate::source::{Lfo, Waveform};
+
+        let mut voice: Voice<f32> = Voice::new(44100.0);
+        let modulation = voice.modulation_mut();
+        let lfo = modulation.add_lfo(Lfo::new(Waveform::Sine, 5.0, 44100.0));
+        modulation.connect(Route::new(lfo, Parameter::Pitch, 1.0, Polarity::Bipolar));
+        modulation.connect(Route::new(
+            Source::Controller(1),
+            Parameter::Tenseness,
+            0.4,
+            Polarity::Unipolar,
+        ));
+        voice.set_tenseness(0.5);
+        voice.set_controller(1, 127);
+        voice.note_on(57, 100);
+
+        let mut block = [0.0; 2205];
+        voice.render_block(&mut block);
+        assert!(voice.frequency() > 220.0 * 1.05);
+        assert!((voice.glottis().tenseness - 0.9).abs() < 1e-9);
+        assert!(block.iter().all(|x| x.is_finite()));
+    }
+
+    #[test]
+    fn test_automation() {
+        let mut automation = Automation::new();
+        automation.insert(
+            Parameter::Pitch,
+            Keyframe::new(0.0, 57.0, Interpolation::Step),
+        );
+        automation.insert(
+            Parameter::Pitch,
+            Keyframe::new(0.5, 69.0, Interpolation::Step),
+        );
+        automation.insert(
+            Parameter::Tenseness,
+            Keyframe::new(0.0, 0.2, Interpolation::LINEAR),
+        );
+        automation.insert(
+            Parameter::Tenseness,
+            Keyframe::new(1.0, 0.8, Interpolation::LINEAR),
+        );
+
+        let mut voice: Voice<f32> = Voice::new(44100.0);
+        voice.set_automation(Some(automation));
+        voice.note_on(60, 100);
+
+        // the step lands on the keyframe's sample
+        let mut block = vec![0.0; 22050];
+        voice.render_block(&mut block);
+        assert!((voice.frequency() - 220.0).abs() < 1e-3);
+        voice.render_block(&mut [0.0]);
+        assert!((voice.frequency() - 440.0).abs() < 1e-3);
+        assert!((voice.glottis().tenseness - 0.5).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,278 @@
+//! Routing of modulation sources to the parameters of a voice.
+
+use crate::filter::stateful::envelope::Envelope;
+use crate::source::Lfo;
+
+/// The number of midi continuous controllers.
+const CONTROLLERS: usize = 128;
+
+/// A parameter of a [`Voice`](super::Voice) that can be modulated or automated.
+///
+/// Modulation offsets a parameter from its set value, whereas
+/// [automation](super::automation) sets the value itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Parameter {
+    /// Pitch in semitones (a midi pitch when automated).
+    Pitch,
+    /// Intensity as a fraction of the note's intensity (the voice's dynamics when automated).
+    Intensity,
+    /// Glottal tenseness in range [0-1].
+    Tenseness,
+    /// Position of the tongue along the tract in range [0-1].
+    TongueIndex,
+    /// Height of the tongue in tract diameter units.
+    TongueDiameter,
+    /// Opening of the lips in tract diameter units.
+    LipDiameter,
+    /// Opening of the velum in range [0-1].
+    Velum,
+}
+
+impl Parameter {
+    /// Returns whether the parameter changes the shape of the tract.
+    pub fn is_articulatory(self) -> bool {
+        matches!(
+            self,
+            Parameter::TongueIndex | Parameter::TongueDiameter | Parameter::LipDiameter
+        )
+    }
+}
+
+/// A source of modulation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Source {
+    /// An oscillator of the matrix (by index), in range [-1, 1].
+    Lfo(usize),
+    /// An envelope of the matrix (by index), triggered by each note.
+    Envelope(usize),
+    /// The velocity of the current note in range [0-1].
+    Velocity,
+    /// A midi continuous controller (by number) in range [0-1].
+    Controller(u8),
+}
+
+impl Source {
+    /// Returns whether the source's natural range is [-1, 1] rather than [0-1].
+    fn is_bipolar(self) -> bool {
+        matches!(self, Source::Lfo(_))
+    }
+}
+
+/// The range to which a source is mapped before scaling by a route's depth.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Polarity {
+    /// Range [0-1].
+    Unipolar,
+    /// Range [-1, 1].
+    Bipolar,
+}
+
+/// A connection from a source to a parameter.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Route {
+    pub source: Source,
+    pub parameter: Parameter,
+    /// The offset applied to the parameter at the extreme of the source's range (negative to
+    /// invert).
+    pub depth: f64,
+    pub polarity: Polarity,
+}
+
+impl Route {
+    pub fn new(source: Source, parameter: Parameter, depth: f64, polarity: Polarity) -> Route {
+        Route {
+            source,
+            parameter,
+            depth,
+            polarity,
+        }
+    }
+}
+
+/// A set of modulation sources and the routes from them to voice parameters.
+///
+/// The offset of each parameter is the sum of every route to it.
+#[derive(Clone, Debug)]
+pub struct ModulationMatrix {
+    lfos: Vec<Lfo>,
+    envelopes: Vec<Envelope>,
+    routes: Vec<Route>,
+    /// The velocity of the current note in range [0-1].
+    velocity: f64,
+    /// The value of each midi continuous controller in range [0-1].
+    controllers: [f64; CONTROLLERS],
+}
+
+impl Default for ModulationMatrix {
+    fn default() -> ModulationMatrix {
+        ModulationMatrix::new()
+    }
+}
+
+impl ModulationMatrix {
+    /// Creates a matrix without sources or routes.
+    pub fn new() -> ModulationMatrix {
+        ModulationMatrix {
+            lfos: Vec::new(),
+            envelopes: Vec::new(),
+            routes: Vec::new(),
+            velocity: 0.0,
+            controllers: [0.0; CONTROLLERS],
+        }
+    }
+
+    /// Adds an oscillator, returning its source.
+    pub fn add_lfo(&mut self, lfo: Lfo) -> Source {
+        self.lfos.push(lfo);
+        Source::Lfo(self.lfos.len() - 1)
+    }
+
+    /// Adds an envelope, returning its source.
+    pub fn add_envelope(&mut self, envelope: Envelope) -> Source {
+        self.envelopes.push(envelope);
+        Source::Envelope(self.envelopes.len() - 1)
+    }
+
+    /// Returns the matrix's oscillators.
+    pub fn lfos_mut(&mut self) -> &mut [Lfo] {
+        &mut self.lfos
+    }
+
+    /// Returns the matrix's envelopes.
+    pub fn envelopes_mut(&mut self) -> &mut [Envelope] {
+        &mut self.envelopes
+    }
+
+    /// Adds a route from a source to a parameter.
+    pub fn connect(&mut self, route: Route) {
+        self.routes.push(route);
+    }
+
+    /// Removes every route from a source to a parameter.
+    pub fn disconnect(&mut self, source: Source, parameter: Parameter) {
+        self.routes
+            .retain(|route| route.source != source || route.parameter != parameter);
+    }
+
+    /// Returns the matrix's routes.
+    pub fn routes(&self) -> &[Route] {
+        &self.routes
+    }
+
+    /// Returns whether any route modulates a parameter.
+    pub fn is_routed(&self, parameter: Parameter) -> bool {
+        self.routes.iter().any(|route| route.parameter == parameter)
+    }
+
+    /// Sets a midi continuous controller's value (0-127).
+    pub fn set_controller(&mut self, controller: u8, value: u8) {
+        if let Some(slot) = self.controllers.get_mut(controller as usize) {
+            *slot = f64::from(value.min(127)) / 127.0;
+        }
+    }
+
+    /// Sets the velocity and triggers every envelope at the start of a note.
+    pub fn note_on(&mut self, velocity: f64) {
+        self.velocity = velocity;
+        self.envelopes.iter_mut().for_each(Envelope::note_on);
+    }
+
+    /// Releases every envelope.
+    pub fn note_off(&mut self) {
+        self.envelopes.iter_mut().for_each(Envelope::note_off);
+    }
+
+    /// Advances every oscillator and envelope by `delta_time` seconds.
+    pub fn advance(&mut self, delta_time: f64) {
+        for lfo in &mut self.lfos {
+            lfo.advance(delta_time);
+        }
+        for envelope in &mut self.envelopes {
+            envelope.advance(delta_time);
+        }
+    }
+
+    /// Returns the current value of a source in its natural range.
+    pub fn value(&self, source: Source) -> f64 {
+        match source {
+            Source::Lfo(index) => self.lfos.get(index).map_or(0.0, Lfo::value),
+            Source::Envelope(index) => self.envelopes.get(index).map_or(0.0, Envelope::level),
+            Source::Velocity => self.velocity,
+            Source::Controller(controller) => self
+                .controllers
+                .get(controller as usize)
+                .copied()
+                .unwrap_or(0.0),
+        }
+    }
+
+    /// Returns the total offset applied to a parameter.
+    pub fn offset(&self, parameter: Parameter) -> f64 {
+        self.routes
+            .iter()
+            .filter(|route| route.parameter == parameter)
+            .map(|route| {
+                let value = self.value(route.source);
+                let value = match (route.source.is_bipolar(), route.polarity) {
+                    (true, Polarity::Unipolar) => (value + 1.0) / 2.0,
+                    (false, Polarity::Bipolar) => value * 2.0 - 1.0,
+                    _ => value,
+                };
+                value * route.depth
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::stateful::envelope::{Curve, Segment};
+    use crate::source::Waveform;
+
+    #[test]
+    fn test_routing() {
+        let mut matrix = ModulationMatrix::new();
+        let lfo = matrix.add_lfo(Lfo::new(Waveform::Triangle, 1.0, 4.0));
+        let envelope = matrix.add_envelope(Envelope::new(
+            vec![Segment::new(1.0, 1.0, Curve::Linear)],
+            None,
+            4.0,
+        ));
+        matrix.connect(Route::new(lfo, Parameter::Pitch, 2.0, Polarity::Bipolar));
+        matrix.connect(Route::new(
+            lfo,
+            Parameter::Tenseness,
+            0.2,
+            Polarity::Unipolar,
+        ));
+        matrix.connect(Route::new(
+            envelope,
+            Parameter::Velum,
+            -1.0,
+            Polarity::Bipolar,
+        ));
+        matrix.connect(Route::new(
+            Source::Controller(1),
+            Parameter::Pitch,
+            1.0,
+            Polarity::Unipolar,
+        ));
+        assert!(!matrix.is_routed(Parameter::Intensity));
+
+        matrix.note_on(1.0);
+        matrix.set_controller(1, 127);
+        matrix.advance(0.25);
+        assert!((matrix.offset(Parameter::Pitch) - 3.0).abs() < 1e-9);
+        assert!((matrix.offset(Parameter::Tenseness) - 0.2).abs() < 1e-9);
+        assert!((matrix.offset(Parameter::Velum) - 0.5).abs() < 1e-9);
+
+        matrix.disconnect(Source::Controller(1), Parameter::Pitch);
+        matrix.advance(0.5);
+        assert!((matrix.offset(Parameter::Pitch) + 2.0).abs() < 1e-9);
+    }
+}
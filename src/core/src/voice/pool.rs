@@ -0,0 +1,370 @@
+//! Allocation of notes to a fixed number of voices.
+
+use super::{Command, Voice};
+use crate::filter::stateful::tract::BLOCK_SIZE;
+use crate::float::Float;
+
+/// The most notes that can be held at once (one per MIDI pitch).
+const MAX_HELD_NOTES: usize = 128;
+
+/// How notes are allocated to voices.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PolyphonyMode {
+    /// A single voice, restarting its envelope for each note.
+    Mono,
+    /// A single voice, gliding between overlapping notes without a new onset.
+    Legato,
+    /// A voice for each note, up to the pool's polyphony.
+    Poly,
+}
+
+/// Which voice is taken over when every voice is in use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StealPolicy {
+    /// Steal the voice whose note started first.
+    Oldest,
+    /// Steal the voice with the lowest intensity.
+    Quietest,
+}
+
+/// A polyphonic set of voices, allocating notes between them.
+///
+/// Released voices keep sounding until their release tail falls silent, and are preferred over
+/// held voices when a voice must be stolen.
+#[derive(Debug)]
+pub struct VoicePool<T: Float = f64> {
+    /// The voice from which new voices are cloned.
+    prototype: Voice<T>,
+    voices: Vec<Voice<T>>,
+    /// The order in which each voice's note started.
+    started: Vec<u64>,
+    /// The number of notes started.
+    counter: u64,
+    mode: PolyphonyMode,
+    policy: StealPolicy,
+    /// The held notes in the order they were pressed (used by mono and legato modes).
+    ///
+    /// Capacity for every pitch is reserved up front (and again when cloned) so notes are held
+    /// without allocating on the audio thread.
+    held: Vec<(u8, u8)>,
+    /// The output of a single voice for the current block.
+    scratch: [T; BLOCK_SIZE],
+}
+
+impl<T: Float> VoicePool<T> {
+    /// Creates a pool of `polyphony` default voices.
+    pub fn new(polyphony: usize, sample_rate: f64) -> VoicePool<T> {
+        VoicePool::from_voice(Voice::new(sample_rate), polyphony)
+    }
+
+    /// Creates a pool of `polyphony` copies of a voice.
+    pub fn from_voice(voice: Voice<T>, polyphony: usize) -> VoicePool<T> {
+        let polyphony = polyphony.max(1);
+        VoicePool {
+            voices: vec![voice.clone(); polyphony],
+            started: vec![0; polyphony],
+            prototype: voice,
+            counter: 0,
+            mode: PolyphonyMode::Poly,
+            policy: StealPolicy::Oldest,
+            held: Vec::with_capacity(MAX_HELD_NOTES),
+            scratch: [T::zero(); BLOCK_SIZE],
+        }
+    }
+
+    /// Sets the number of voices (at least 1), silencing any voices removed.
+    pub fn set_polyphony(&mut self, polyphony: usize) {
+        let polyphony = polyphony.max(1);
+        self.voices.resize(polyphony, self.prototype.clone());
+        self.started.resize(polyphony, 0);
+    }
+
+    /// Returns the number of voices.
+    pub fn polyphony(&self) -> usize {
+        self.voices.len()
+    }
+
+    /// Sets how notes are allocated to voices, releasing every note.
+    pub fn set_mode(&mut self, mode: PolyphonyMode) {
+        if mode != self.mode {
+            self.all_notes_off();
+            self.mode = mode;
+        }
+    }
+
+    /// Returns how notes are allocated to voices.
+    pub fn mode(&self) -> PolyphonyMode {
+        self.mode
+    }
+
+    /// Sets which voice is taken over when every voice is in use.
+    pub fn set_steal_policy(&mut self, policy: StealPolicy) {
+        self.policy = policy;
+    }
+
+    /// Returns which voice is taken over when every voice is in use.
+    pub fn steal_policy(&self) -> StealPolicy {
+        self.policy
+    }
+
+    /// Starts a note at a midi pitch and velocity, ignoring pitches above 127.
+    pub fn note_on(&mut self, pitch: u8, velocity: u8) {
+        if usize::from(pitch) >= MAX_HELD_NOTES {
+            return;
+        }
+        match self.mode {
+            PolyphonyMode::Mono | PolyphonyMode::Legato => {
+                let legato = self.mode == PolyphonyMode::Legato && !self.held.is_empty();
+                self.held.retain(|&(held, _)| held != pitch);
+                self.held.push((pitch, velocity));
+                if legato {
+                    self.voices[0].legato(pitch);
+                } else {
+                    self.start(0, pitch, velocity);
+                }
+            }
+            PolyphonyMode::Poly => {
+                let index = self.allocate(pitch);
+                self.start(index, pitch, velocity);
+            }
+        }
+    }
+
+    /// Releases a note at a midi pitch.
+    ///
+    /// In mono and legato modes, releasing the sounding note returns to the most recent note
+    /// still held.
+    pub fn note_off(&mut self, pitch: u8) {
+        match self.mode {
+            PolyphonyMode::Mono | PolyphonyMode::Legato => {
+                let sounding = self.held.last().map(|&(held, _)| held);
+                self.held.retain(|&(held, _)| held != pitch);
+                if sounding != Some(pitch) {
+                    return;
+                }
+                match self.held.last() {
+                    Some(&(previous, velocity)) => {
+                        if self.mode == PolyphonyMode::Legato {
+                            self.voices[0].legato(previous);
+                        } else {
+                            self.start(0, previous, velocity);
+                        }
+                    }
+                    None => self.voices[0].note_off(),
+                }
+            }
+            PolyphonyMode::Poly => {
+                for voice in &mut self.voices {
+                    if voice.is_held() && voice.note() == Some(pitch) {
+                        voice.note_off();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Applies a command from a [`VoiceController`](super::VoiceController), sending notes
+    /// through the allocator and everything else to every voice.
+    ///
+    /// Notes with pitches above 127 are ignored.
+    pub fn apply(&mut self, command: Command) {
+        match command {
+            Command::NoteOn { pitch, velocity } => self.note_on(pitch, velocity),
+            Command::NoteOff { pitch } => self.note_off(pitch),
+            Command::Reset => {
+                self.held.clear();
+                self.voices.iter_mut().for_each(Voice::reset);
+            }
+            command => {
+                for voice in &mut self.voices {
+                    voice.apply(command);
+                }
+            }
+        }
+    }
+
+    /// Releases every note, letting each voice's release tail fall silent.
+    pub fn all_notes_off(&mut self) {
+        self.held.clear();
+        for voice in &mut self.voices {
+            voice.note_off();
+        }
+    }
+
+    /// Returns the number of voices producing sound.
+    pub fn active_voices(&self) -> usize {
+        self.voices.iter().filter(|voice| voice.is_active()).count()
+    }
+
+    /// Returns the pool's voices.
+    pub fn voices(&self) -> &[Voice<T>] {
+        &self.voices
+    }
+
+    /// Returns the pool's voices for direct control (such as articulation).
+    pub fn voices_mut(&mut self) -> &mut [Voice<T>] {
+        &mut self.voices
+    }
+
+    /// Renders the sum of every active voice, overwriting `output`.
+    pub fn render_block(&mut self, output: &mut [T]) {
+        output.iter_mut().for_each(|sample| *sample = T::zero());
+        for chunk in output.chunks_mut(BLOCK_SIZE) {
+            let scratch = &mut self.scratch[..chunk.len()];
+            for voice in self.voices.iter_mut().filter(|voice| voice.is_active()) {
+                voice.render_block(scratch);
+                for (sample, &voice) in chunk.iter_mut().zip(scratch.iter()) {
+                    *sample += voice;
+                }
+            }
+        }
+    }
+
+    /// Starts a note on the voice at `index`.
+    fn start(&mut self, index: usize, pitch: u8, velocity: u8) {
+        self.counter += 1;
+        self.started[index] = self.counter;
+        self.voices[index].note_on(pitch, velocity);
+    }
+
+    /// Returns the index of the voice to play a note, stealing one if every voice is in use.
+    fn allocate(&self, pitch: u8) -> usize {
+        // retrigger a voice already singing the pitch
+        if let Some(index) = self
+            .voices
+            .iter()
+            .position(|voice| voice.is_active() && voice.note() == Some(pitch))
+        {
+            return index;
+        }
+        if let Some(index) = self.voices.iter().position(|voice| !voice.is_active()) {
+            return index;
+        }
+
+        // prefer stealing from voices in their release tail
+        let released = self.voices.iter().any(|voice| !voice.is_held());
+        let candidates =
+            (0..self.voices.len()).filter(|&index| !released || !self.voices[index].is_held());
+        match self.policy {
+            StealPolicy::Oldest => candidates.min_by_key(|&index| self.started[index]),
+            StealPolicy::Quietest => candidates.min_by(|&a, &b| {
+                let (a, b) = (self.voices[a].intensity(), self.voices[b].intensity());
+                a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+            }),
+        }
+        .unwrap_or(0)
+    }
+}
+
+impl<T: Float> Clone for VoicePool<T> {
+    fn clone(&self) -> VoicePool<T> {
+        let mut held = Vec::with_capacity(MAX_HELD_NOTES);
+        held.extend_from_slice(&self.held);
+        VoicePool {
+            prototype: self.prototype.clone(),
+            voices: self.voices.clone(),
+            started: self.started.clone(),
+            counter: self.counter,
+            mode: self.mode,
+            policy: self.policy,
+            held,
+            scratch: self.scratch,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(pool: &mut VoicePool<f32>, seconds: f64) {
+        let mut buffer = vec![0.0; (seconds * 44100.0) as usize];
+        pool.render_block(&mut buffer);
+        assert!(buffer.iter().all(|x| x.is_finite()));
+    }
+
+    fn notes(pool: &VoicePool<f32>) -> Vec<Option<u8>> {
+        pool.voices().iter().map(|voice| voice.note()).collect()
+    }
+
+    #[test]
+    fn test_poly_stealing() {
+        let mut pool: VoicePool<f32> = VoicePool::new(3, 44100.0);
+        pool.note_on(60, 100);
+        pool.note_on(64, 100);
+        pool.note_on(67, 100);
+        render(&mut pool, 0.1);
+        assert_eq!(pool.active_voices(), 3);
+
+        // every voice is held so the oldest is stolen
+        pool.note_on(72, 100);
+        assert_eq!(notes(&pool), vec![Some(72), Some(64), Some(67)]);
+
+        // released voices are stolen before held ones
+        pool.note_off(67);
+        pool.note_on(76, 100);
+        assert_eq!(notes(&pool), vec![Some(72), Some(64), Some(76)]);
+    }
+
+    #[test]
+    fn test_quietest_stealing() {
+        let mut pool: VoicePool<f32> = VoicePool::new(2, 44100.0);
+        pool.set_steal_policy(StealPolicy::Quietest);
+        pool.note_on(60, 20);
+        pool.note_on(64, 127);
+        render(&mut pool, 0.2);
+        pool.note_on(67, 100);
+        assert_eq!(notes(&pool), vec![Some(67), Some(64)]);
+    }
+
+    #[test]
+    fn test_release_tail() {
+        let mut pool: VoicePool<f32> = VoicePool::new(2, 44100.0);
+        pool.note_on(60, 100);
+        render(&mut pool, 0.1);
+        pool.note_off(60);
+        render(&mut pool, 0.01);
+        assert_eq!(pool.active_voices(), 1);
+        render(&mut pool, 1.0);
+        assert_eq!(pool.active_voices(), 0);
+    }
+
+    #[test]
+    fn test_mono_note_priority() {
+        for mode in [PolyphonyMode::Mono, PolyphonyMode::Legato] {
+            let mut pool: VoicePool<f32> = VoicePool::new(4, 44100.0);
+            pool.set_mode(mode);
+            pool.note_on(60, 100);
+            pool.note_on(64, 100);
+            assert_eq!(pool.voices()[0].note(), Some(64));
+            assert_eq!(pool.active_voices(), 1);
+
+            // releasing the sounding note returns to the one still held
+            pool.note_off(64);
+            assert_eq!(pool.voices()[0].note(), Some(60));
+            assert!(pool.voices()[0].is_held());
+
+            pool.note_off(60);
+            assert!(!pool.voices()[0].is_held());
+
+            // pitches above 127 are ignored
+            for pitch in 0..=255 {
+                pool.note_on(pitch, 100);
+            }
+            assert_eq!(pool.voices()[0].note(), Some(127));
+            pool.apply(Command::NoteOn {
+                pitch: 200,
+                velocity: 100,
+            });
+            assert_eq!(pool.voices()[0].note(), Some(127));
+
+            // a clone keeps the held notes
+            let mut clone = pool.clone();
+            clone.note_off(127);
+            assert_eq!(clone.voices()[0].note(), Some(126));
+            assert!(clone.voices()[0].is_held());
+        }
+    }
+}
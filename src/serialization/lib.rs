@@ -0,0 +1,9 @@
+//! Reading of MIDI files and MusicXML scores.
+//!
+//! MusicXML scores are read into a `MidiSong` by `musicxml::read_musicxml`, to be synthesized
+//! as any MIDI file. `wav` and `synthesis` are yet to be ported from synthrs and are not built.
+
+#![allow(dead_code)]
+
+pub mod midi;
+pub mod musicxml;
@@ -134,6 +134,77 @@ pub struct MidiTrack {
     pub max_time: usize,
 }
 
+/// A note with its extent in ticks and the lyric syllable sung on it, if any.
+/// Notes without a syllable continue the previous one (melisma).
+#[derive(Clone, Debug, PartialEq)]
+pub struct MidiNote {
+    pub note: usize,
+    pub velocity: usize,
+    pub channel: u8,
+    /// The index of the track containing the note
+    pub track: usize,
+    pub start: usize,
+    pub end: usize,
+    pub syllable: Option<String>,
+}
+
+impl MidiSong {
+    /// Collects the notes of every track in order of their start, or only those on `channel`,
+    /// attaching each lyric syllable to the first note starting at or after it.
+    ///
+    /// Syllables attach to the notes of their own track, so the lyrics of one part never land
+    /// on another. Lyrics in tracks without notes (such as the words track of karaoke files)
+    /// attach to the notes of the tracks without lyrics.
+    ///
+    /// Syllables are taken from lyric events, or failing those from the text events of
+    /// karaoke (.kar) files, whose `@` headers are skipped and `/` and `\` line breaks are
+    /// stripped.
+    pub fn notes(&self, channel: Option<u8>) -> Vec<MidiNote> {
+        let mut notes: Vec<MidiNote> = Vec::new();
+        // notes of tracks without lyrics, and lyrics of tracks without notes
+        let mut unsung: Vec<MidiNote> = Vec::new();
+        let mut words: Vec<(usize, String)> = Vec::new();
+
+        for (index, track) in self.tracks.iter().enumerate() {
+            let syllables = track.syllables();
+            if !track.has_notes() {
+                words.extend(syllables);
+                continue;
+            }
+
+            let mut track_notes = track.notes(index, channel, self.max_time);
+            if syllables.is_empty() {
+                unsung.append(&mut track_notes);
+            } else {
+                attach_syllables(&mut track_notes, syllables);
+                notes.append(&mut track_notes);
+            }
+        }
+
+        unsung.sort_by_key(|note| note.start);
+        words.sort_by_key(|&(time, _)| time);
+        attach_syllables(&mut unsung, words);
+        notes.append(&mut unsung);
+        notes.sort_by_key(|note| note.start);
+        notes
+    }
+}
+
+/// Attaches each syllable to the first note starting at or after it, given both in order.
+fn attach_syllables(notes: &mut [MidiNote], syllables: Vec<(usize, String)>) {
+    let mut cursor = 0;
+    for (time, syllable) in syllables {
+        while cursor < notes.len() && notes[cursor].start < time {
+            cursor += 1;
+        }
+        if cursor == notes.len() {
+            break;
+        }
+        notes[cursor].syllable = Some(syllable);
+        cursor += 1;
+    }
+}
+
 impl MidiTrack {
     fn new() -> MidiTrack {
         let events: Vec<MidiEvent> = Vec::new();
@@ -142,9 +213,76 @@ impl MidiTrack {
             max_time: 0,
         }
     }
+
+    fn has_notes(&self) -> bool {
+        self.events
+            .iter()
+            .any(|event| event.event_type == EventType::NoteOn)
+    }
+
+    /// Returns the notes of the track (the track at `index` of its song) in order of their
+    /// start, or only those on `channel`.
+    fn notes(&self, index: usize, channel: Option<u8>, max_time: usize) -> Vec<MidiNote> {
+        let mut notes: Vec<MidiNote> = Vec::new();
+        for (i, event) in self.events.iter().enumerate() {
+            if event.event_type != EventType::NoteOn
+                || event.is_note_terminating()
+                || channel.is_some_and(|channel| channel != event.channel)
+            {
+                continue;
+            }
+
+            let end = self.events[i + 1..]
+                .iter()
+                .find(|cursor| {
+                    cursor.value1 == event.value1
+                        && cursor.channel == event.channel
+                        && cursor.is_note_terminating()
+                })
+                .map_or(max_time, |cursor| cursor.time);
+
+            notes.push(MidiNote {
+                note: event.value1,
+                velocity: event.value2.unwrap_or(0),
+                channel: event.channel,
+                track: index,
+                start: event.time,
+                end,
+                syllable: None,
+            });
+        }
+        notes.sort_by_key(|note| note.start);
+        notes
+    }
+
+    /// Returns the lyric syllables of the track with their times, in order.
+    fn syllables(&self) -> Vec<(usize, String)> {
+        let texts = |meta_event_type: MetaEventType| {
+            let mut texts: Vec<(usize, String)> = self
+                .events
+                .iter()
+                .filter(|event| event.meta_event_type == Some(meta_event_type))
+                .filter_map(|event| event.text.clone().map(|text| (event.time, text)))
+                .collect();
+            texts.sort_by_key(|&(time, _)| time);
+            texts
+        };
+
+        let lyrics = texts(MetaEventType::LyricText);
+        if !lyrics.is_empty() {
+            return lyrics;
+        }
+
+        texts(MetaEventType::TextEvent)
+            .into_iter()
+            .filter(|(_, text)| !text.starts_with('@'))
+            .map(|(time, text)| (time, text.trim_start_matches(&['/', '\\'][..]).to_string()))
+            .filter(|(_, text)| !text.is_empty())
+            .collect()
+    }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct MidiEvent {
     pub event_type: EventType,
     pub system_event_type: Option<SystemEventType>,
@@ -153,11 +291,13 @@ pub struct MidiEvent {
     pub channel: u8,
     pub value1: usize,
     pub value2: Option<usize>,
+    /// The text of lyric and text meta events
+    pub text: Option<String>,
 }
 
 impl MidiEvent {
     // NoteOn with velocity 0 == NoteOff
-    pub fn is_note_terminating(self) -> bool {
+    pub fn is_note_terminating(&self) -> bool {
         (self.event_type == EventType::NoteOff)
             || self.event_type == EventType::NoteOn
                 && self.value2.is_some()
@@ -259,6 +399,7 @@ where
             channel: self.running_channel.unwrap_or(0),
             value1,
             value2,
+            text: None,
         })
     }
 
@@ -334,6 +475,23 @@ where
                     channel: self.running_channel.unwrap_or(0),
                     value1: tempo,
                     value2: None,
+                    text: None,
+                }));
+            }
+
+            Some(MetaEventType::LyricText) | Some(MetaEventType::TextEvent) => {
+                let mut bytes = vec![0u8; meta_data_size];
+                try_opt!(self.reader.read_exact(&mut bytes));
+
+                return Some(Ok(MidiEvent {
+                    event_type: self.running_status.unwrap_or(EventType::Unknown),
+                    system_event_type: Some(system_event_type),
+                    meta_event_type: meta_message_type,
+                    time: self.time,
+                    channel: self.running_channel.unwrap_or(0),
+                    value1: meta_data_size,
+                    value2: None,
+                    text: Some(String::from_utf8_lossy(&bytes).into_owned()),
                 }));
             }
 
@@ -386,7 +544,7 @@ where
         let mut value = (octet & 0b0111_1111) as usize;
         while octet >= 0b1000_0000 {
             octet = self.reader.read_u8()?;
-            value = (value << 7) as usize + (octet & 0b0111_1111) as usize;
+            value = (value << 7) + (octet & 0b0111_1111) as usize;
         }
 
         Ok(value)
@@ -451,7 +609,7 @@ where
 
 /// Convenience method for parsing a `Result<MidiSong>` from a filepath.
 /// ```
-/// use synthrs::midi::read_midi_file;
+/// use serialization::midi::read_midi_file;
 ///
 /// let song = read_midi_file("tests/assets/test.mid");
 /// ```
@@ -466,7 +624,7 @@ pub fn read_midi_file<P: AsRef<Path>>(path: P) -> Result<MidiSong> {
 ///
 /// From a file
 /// ```
-/// use synthrs::midi::read_midi;
+/// use serialization::midi::read_midi;
 /// use std::fs::File;
 /// use std::io::BufReader;
 ///
@@ -477,7 +635,7 @@ pub fn read_midi_file<P: AsRef<Path>>(path: P) -> Result<MidiSong> {
 ///
 /// From a `&[u8]`
 /// ```
-/// use synthrs::midi::read_midi;
+/// use serialization::midi::read_midi;
 /// use std::fs::File;
 /// use std::io::{Cursor, Seek, BufReader, Read};
 ///
@@ -510,7 +668,7 @@ where
     for track in &song.tracks {
         for event in &track.events {
             if let Some(MetaEventType::TempoSetting) = event.meta_event_type {
-                song.bpm = (60_000_000.0 / event.value1 as f64) as f64;
+                song.bpm = 60_000_000.0 / event.value1 as f64;
                 break;
             }
         }
@@ -605,9 +763,118 @@ mod tests {
         assert_eq!(song.max_time, 5640);
     }
 
+    #[test]
+    fn it_parses_lyrics_and_attaches_them_to_notes() {
+        #[rustfmt::skip]
+        let bytes: Vec<u8> = vec![
+            0x4d, 0x54, 0x68, 0x64, 0, 0, 0, 6, 0, 0, 0, 1, 0, 96, // MThd
+            0x4d, 0x54, 0x72, 0x6b, 0, 0, 0, 49, // MTrk
+            0x00, 0xff, 0x05, 3, b'H', b'e', b'l', // lyric
+            0x00, 0x90, 60, 64,
+            0x60, 0x80, 60, 0,
+            0x00, 0xff, 0x05, 2, b'l', b'o', // lyric
+            0x00, 0x90, 62, 64,
+            0x60, 0x80, 62, 0,
+            0x00, 0x90, 64, 64, // melisma
+            0x60, 0x80, 64, 0,
+            0x00, 0xff, 0x01, 4, b'd', b'o', b'n', b'e', // text
+            0x00, 0xff, 0x2f, 0, // end of track
+        ];
+        let song = read_midi(&mut std::io::Cursor::new(bytes)).expect("failed");
+        let events = &song.tracks[0].events;
+
+        assert_eq!(events[0].meta_event_type, Some(MetaEventType::LyricText));
+        assert_eq!(events[0].time, 0);
+        assert_eq!(events[0].text.as_deref(), Some("Hel"));
+        assert_eq!(events[3].text.as_deref(), Some("lo"));
+        assert_eq!(events[3].time, 96);
+        assert_eq!(events[8].meta_event_type, Some(MetaEventType::TextEvent));
+        assert_eq!(events[8].time, 288);
+        assert_eq!(events[8].text.as_deref(), Some("done"));
+
+        let notes = song.notes(None);
+        assert_eq!(notes.len(), 3);
+        assert_eq!((notes[0].note, notes[0].start, notes[0].end), (60, 0, 96));
+        assert_eq!(notes[0].syllable.as_deref(), Some("Hel"));
+        assert_eq!(notes[1].syllable.as_deref(), Some("lo"));
+        assert_eq!(
+            (notes[2].note, notes[2].start, notes[2].end),
+            (64, 192, 288)
+        );
+        assert_eq!(notes[2].syllable, None);
+    }
+
+    #[test]
+    fn it_attaches_lyrics_to_the_notes_of_their_own_track() {
+        #[rustfmt::skip]
+        let bytes: Vec<u8> = vec![
+            0x4d, 0x54, 0x68, 0x64, 0, 0, 0, 6, 0, 1, 0, 2, 0, 96, // MThd
+            0x4d, 0x54, 0x72, 0x6b, 0, 0, 0, 32, // MTrk (sung on channel 0)
+            0x00, 0xff, 0x05, 2, b'L', b'a', // lyric
+            0x00, 0x90, 60, 64,
+            0x60, 0x80, 60, 0,
+            0x00, 0xff, 0x05, 2, b'l', b'a', // lyric
+            0x00, 0x90, 62, 64,
+            0x60, 0x80, 62, 0,
+            0x00, 0xff, 0x2f, 0, // end of track
+            0x4d, 0x54, 0x72, 0x6b, 0, 0, 0, 20, // MTrk (accompaniment on channel 1)
+            0x00, 0x91, 57, 64,
+            0x60, 0x81, 57, 0,
+            0x00, 0x91, 59, 64,
+            0x60, 0x81, 59, 0,
+            0x00, 0xff, 0x2f, 0, // end of track
+        ];
+        let song = read_midi(&mut std::io::Cursor::new(bytes)).expect("failed");
+
+        let notes = song.notes(None);
+        let sung: Vec<_> = notes
+            .iter()
+            .map(|note| (note.note, note.track, note.syllable.as_deref()))
+            .collect();
+        assert_eq!(
+            sung,
+            [
+                (60, 0, Some("La")),
+                (57, 1, None),
+                (62, 0, Some("la")),
+                (59, 1, None)
+            ]
+        );
+
+        let accompaniment = song.notes(Some(1));
+        assert_eq!(accompaniment.len(), 2);
+        assert!(accompaniment
+            .iter()
+            .all(|note| note.channel == 1 && note.syllable.is_none()));
+
+        // the words track of a karaoke file has no notes of its own
+        #[rustfmt::skip]
+        let bytes: Vec<u8> = vec![
+            0x4d, 0x54, 0x68, 0x64, 0, 0, 0, 6, 0, 1, 0, 2, 0, 96, // MThd
+            0x4d, 0x54, 0x72, 0x6b, 0, 0, 0, 28, // MTrk (words)
+            0x00, 0xff, 0x01, 6, b'@', b'K', b'M', b'I', b'D', b'I', // header
+            0x00, 0xff, 0x01, 3, b'H', b'e', b'l', // text
+            0x60, 0xff, 0x01, 3, b'/', b'l', b'o', // text
+            0x00, 0xff, 0x2f, 0, // end of track
+            0x4d, 0x54, 0x72, 0x6b, 0, 0, 0, 20, // MTrk (melody)
+            0x00, 0x90, 60, 64,
+            0x60, 0x80, 60, 0,
+            0x00, 0x90, 62, 64,
+            0x60, 0x80, 62, 0,
+            0x00, 0xff, 0x2f, 0, // end of track
+        ];
+        let song = read_midi(&mut std::io::Cursor::new(bytes)).expect("failed");
+        let syllables: Vec<_> = song
+            .notes(None)
+            .into_iter()
+            .map(|note| note.syllable)
+            .collect();
+        assert_eq!(syllables, [Some("Hel".to_string()), Some("lo".to_string())]);
+    }
+
     #[test]
     fn it_parses_the_bpm_of_a_midi_file() {
         let song = read_midi_file("tests/assets/running_status.mid").expect("failed");
         assert_eq!(song.bpm as usize, 160);
     }
-}
\ No newline at end of file
+}
@@ -1,4 +0,0 @@
-#![allow(dead_code)]
-
-pub mod midi;
-pub mod wav;
\ No newline at end of file
@@ -0,0 +1,853 @@
+//! MusicXML score import
+//!
+//! Scores are converted into a `MidiSong`, so their notes and lyrics are read just as those of
+//! MIDI files. `read_musicxml` (or `read_musicxml_file`) is the entry point: the song it returns
+//! is synthesized as any MIDI file.
+//! Only uncompressed, partwise scores (`.musicxml` or `.xml`) are supported, and repeats are not
+//! expanded.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Error, ErrorKind, Read, Result};
+use std::path::Path;
+
+use crate::midi::{EventType, MetaEventType, MidiEvent, MidiSong, MidiTrack, SystemEventType};
+
+// https://www.w3.org/2021/06/musicxml40/
+// https://www.w3.org/2021/06/musicxml40/tutorial/midi-compatible-part/
+
+/// MIDI ticks per quarter note at the initial tempo of the score
+const TICKS_PER_QUARTER: usize = 960;
+/// Tempo used until the score sets one, as in MIDI
+const DEFAULT_BPM: f64 = 120.0;
+/// Velocity of notes before the score sets a dynamic, that of `mf`
+const DEFAULT_VELOCITY: usize = 80;
+/// Velocity of `f`, relative to which `dynamics` attributes are given in percent
+const FORTE_VELOCITY: f64 = 90.0;
+
+/// An element of an XML document
+#[derive(Clone, Debug, PartialEq)]
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    fn has(&self, name: &str) -> bool {
+        self.child(name).is_some()
+    }
+
+    /// Returns the trimmed text of a child element
+    fn child_text(&self, name: &str) -> Option<&str> {
+        self.child(name).map(|child| child.text.trim())
+    }
+
+    fn child_number(&self, name: &str) -> Option<f64> {
+        self.child_text(name).and_then(|text| text.parse().ok())
+    }
+}
+
+fn invalid_data(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+/// Returns the duration of a note, backup or forward in quarter notes
+fn read_duration(element: &Element, divisions: f64) -> Result<f64> {
+    let duration = element.child_number("duration").unwrap_or(0.0);
+    if !(duration.is_finite() && duration >= 0.0) {
+        return Err(invalid_data(format!("invalid duration {}", duration)));
+    }
+    Ok(duration / divisions)
+}
+
+/// A minimal XML reader, sufficient for MusicXML: elements, attributes, text, CDATA and
+/// character references. Declarations, processing instructions and comments are skipped.
+struct XmlParser<'a> {
+    source: &'a str,
+    position: usize,
+}
+
+impl<'a> XmlParser<'a> {
+    fn new(source: &'a str) -> XmlParser<'a> {
+        XmlParser {
+            source,
+            position: 0,
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.source[self.position..]
+    }
+
+    fn error(&self, message: &str) -> Error {
+        invalid_data(format!("{} at byte {}", message, self.position))
+    }
+
+    /// Advances past `terminator`, returning what came before it
+    fn take_until(&mut self, terminator: &str) -> Result<&'a str> {
+        match self.rest().find(terminator) {
+            Some(index) => {
+                let taken = &self.rest()[..index];
+                self.position += index + terminator.len();
+                Ok(taken)
+            }
+            None => Err(self.error(&format!("expected `{}`", terminator))),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let trimmed = self.rest().trim_start();
+        self.position = self.source.len() - trimmed.len();
+    }
+
+    fn read_name(&mut self) -> Result<&'a str> {
+        let length = self
+            .rest()
+            .find(|c: char| c.is_whitespace() || c == '>' || c == '/' || c == '=')
+            .unwrap_or_else(|| self.rest().len());
+        if length == 0 {
+            return Err(self.error("expected a name"));
+        }
+        let name = &self.rest()[..length];
+        self.position += length;
+        Ok(name)
+    }
+
+    /// Skips everything that is not an element, such as the prolog and comments
+    fn skip_markup(&mut self) -> Result<()> {
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("<?") {
+                self.take_until("?>")?;
+            } else if self.rest().starts_with("<!--") {
+                self.take_until("-->")?;
+            } else if self.rest().starts_with("<!") {
+                // DOCTYPE, possibly with an internal subset in brackets
+                let mut depth = 0;
+                let end = self.rest().find(|c: char| {
+                    match c {
+                        '[' => depth += 1,
+                        ']' => depth -= 1,
+                        _ => {}
+                    }
+                    c == '>' && depth == 0
+                });
+                match end {
+                    Some(index) => self.position += index + 1,
+                    None => return Err(self.error("unclosed declaration")),
+                }
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse_document(&mut self) -> Result<Element> {
+        self.skip_markup()?;
+        let root = self.parse_element()?;
+        self.skip_markup()?;
+        if !self.rest().is_empty() {
+            return Err(self.error("unexpected content after the root element"));
+        }
+        Ok(root)
+    }
+
+    fn parse_element(&mut self) -> Result<Element> {
+        if !self.rest().starts_with('<') {
+            return Err(self.error("expected an element"));
+        }
+        self.position += 1;
+
+        let mut element = Element {
+            name: self.read_name()?.to_string(),
+            attributes: Vec::new(),
+            children: Vec::new(),
+            text: String::new(),
+        };
+
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("/>") {
+                self.position += 2;
+                return Ok(element);
+            } else if self.rest().starts_with('>') {
+                self.position += 1;
+                break;
+            }
+
+            let key = self.read_name()?.to_string();
+            self.skip_whitespace();
+            if !self.rest().starts_with('=') {
+                return Err(self.error("expected `=`"));
+            }
+            self.position += 1;
+            self.skip_whitespace();
+            let quote = match self.rest().chars().next() {
+                Some(quote @ '"') | Some(quote @ '\'') => quote,
+                _ => return Err(self.error("expected a quoted attribute value")),
+            };
+            self.position += 1;
+            let value = self.take_until(if quote == '"' { "\"" } else { "'" })?;
+            element.attributes.push((key, unescape(value)));
+        }
+
+        loop {
+            let text_length = self.rest().find('<').unwrap_or_else(|| self.rest().len());
+            element
+                .text
+                .push_str(&unescape(&self.rest()[..text_length]));
+            self.position += text_length;
+
+            if self.rest().is_empty() {
+                return Err(self.error(&format!("unclosed element `{}`", element.name)));
+            } else if self.rest().starts_with("</") {
+                self.position += 2;
+                let name = self.read_name()?;
+                if name != element.name {
+                    return Err(self.error(&format!(
+                        "expected `</{}>`, found `</{}>`",
+                        element.name, name
+                    )));
+                }
+                self.take_until(">")?;
+                return Ok(element);
+            } else if self.rest().starts_with("<![CDATA[") {
+                self.position += "<![CDATA[".len();
+                let data = self.take_until("]]>")?;
+                element.text.push_str(data);
+            } else if self.rest().starts_with("<!--") {
+                self.take_until("-->")?;
+            } else if self.rest().starts_with("<?") {
+                self.take_until("?>")?;
+            } else {
+                let child = self.parse_element()?;
+                element.children.push(child);
+            }
+        }
+    }
+}
+
+/// Replaces the predefined entities and character references of XML
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let reference = rest.find(';').map(|end| (&rest[1..end], end));
+        let replacement = reference.and_then(|(name, _)| match name {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ if name.starts_with("#x") => u32::from_str_radix(&name[2..], 16)
+                .ok()
+                .and_then(std::char::from_u32),
+            _ if name.starts_with('#') => name[1..].parse().ok().and_then(std::char::from_u32),
+            _ => None,
+        });
+
+        match (replacement, reference) {
+            (Some(c), Some((_, end))) => {
+                unescaped.push(c);
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                // Unknown entities are kept as they are
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// A sounding note, in quarter notes from the start of the score
+#[derive(Clone, Debug)]
+struct ScoreNote {
+    note: usize,
+    velocity: usize,
+    start: f64,
+    end: f64,
+    syllable: Option<String>,
+}
+
+/// Converts a dynamic marking into a MIDI velocity
+fn dynamic_velocity(marking: &str) -> Option<usize> {
+    match marking {
+        "pppppp" | "ppppp" | "pppp" => Some(8),
+        "ppp" => Some(16),
+        "pp" => Some(33),
+        "p" => Some(49),
+        "mp" => Some(64),
+        "mf" => Some(80),
+        "f" | "sf" | "sfz" | "fz" | "rf" | "rfz" => Some(96),
+        "ff" | "sff" | "sffz" => Some(112),
+        "fff" | "ffff" | "fffff" | "ffffff" => Some(127),
+        "fp" | "sfp" => Some(49),
+        "pf" => Some(96),
+        _ => None,
+    }
+}
+
+/// Converts a `dynamics` attribute, in percent of `f`, into a MIDI velocity
+fn percent_velocity(percent: &str) -> Option<usize> {
+    let percent: f64 = percent.trim().parse().ok()?;
+    Some((percent / 100.0 * FORTE_VELOCITY).round().clamp(1.0, 127.0) as usize)
+}
+
+/// Returns the length of a beat unit in quarter notes
+fn beat_unit_quarters(beat_unit: &str, dots: usize) -> Option<f64> {
+    let quarters = match beat_unit {
+        "long" => 16.0,
+        "breve" => 8.0,
+        "whole" => 4.0,
+        "half" => 2.0,
+        "quarter" => 1.0,
+        "eighth" => 0.5,
+        "16th" => 0.25,
+        "32nd" => 0.125,
+        _ => return None,
+    };
+    // each dot adds half of the previous value
+    Some(quarters * (2.0 - 0.5f64.powi(dots as i32)))
+}
+
+/// Returns the tempo set by a `sound` element in quarter notes per minute
+fn sound_tempo(sound: &Element) -> Option<f64> {
+    sound
+        .attribute("tempo")
+        .and_then(|tempo| tempo.trim().parse().ok())
+}
+
+/// Returns the velocity set by a `sound` element
+fn sound_velocity(sound: &Element) -> Option<usize> {
+    sound.attribute("dynamics").and_then(percent_velocity)
+}
+
+/// Returns the tempo set by a `direction` in quarter notes per minute
+fn direction_tempo(direction: &Element) -> Option<f64> {
+    if let Some(tempo) = direction.child("sound").and_then(sound_tempo) {
+        return Some(tempo);
+    }
+
+    direction
+        .children("direction-type")
+        .filter_map(|direction_type| direction_type.child("metronome"))
+        .find_map(|metronome| {
+            let per_minute: f64 = metronome.child_text("per-minute")?.parse().ok()?;
+            let dots = metronome.children("beat-unit-dot").count();
+            Some(per_minute * beat_unit_quarters(metronome.child_text("beat-unit")?, dots)?)
+        })
+}
+
+/// Returns the velocity set by a `direction`
+fn direction_velocity(direction: &Element) -> Option<usize> {
+    if let Some(velocity) = direction.child("sound").and_then(sound_velocity) {
+        return Some(velocity);
+    }
+
+    direction
+        .children("direction-type")
+        .flat_map(|direction_type| direction_type.children("dynamics"))
+        .flat_map(|dynamics| dynamics.children.iter())
+        .find_map(|marking| dynamic_velocity(&marking.name))
+}
+
+/// Returns the MIDI note of a `pitch` element
+fn pitch_note(pitch: &Element) -> Result<usize> {
+    let step = match pitch.child_text("step") {
+        Some("C") => 0,
+        Some("D") => 2,
+        Some("E") => 4,
+        Some("F") => 5,
+        Some("G") => 7,
+        Some("A") => 9,
+        Some("B") => 11,
+        step => return Err(invalid_data(format!("invalid pitch step {:?}", step))),
+    };
+    let alter = pitch.child_number("alter").unwrap_or(0.0).round() as isize;
+    let octave = pitch
+        .child_number("octave")
+        .ok_or_else(|| invalid_data("pitch without an octave".to_string()))?
+        as isize;
+
+    let note = (octave + 1) * 12 + step + alter;
+    if !(0..128).contains(&note) {
+        return Err(invalid_data(format!(
+            "pitch out of the MIDI range: {}",
+            note
+        )));
+    }
+    Ok(note as usize)
+}
+
+/// Returns the syllable of the first lyric line of a note, if it has text.
+/// Syllables that begin or continue a word end with a hyphen, as in MIDI lyric events, and
+/// elided syllables are joined by a space.
+fn note_syllable(note: &Element) -> Option<String> {
+    let lyric = note
+        .children("lyric")
+        .find(|lyric| lyric.attribute("number").is_none_or(|number| number == "1"))
+        .or_else(|| note.child("lyric"))?;
+
+    let text = lyric
+        .children("text")
+        .map(|text| text.text.trim())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if text.is_empty() {
+        // a lyric holding only an extender continues the previous syllable (melisma)
+        return None;
+    }
+
+    match lyric.child_text("syllabic") {
+        Some("begin") | Some("middle") => Some(format!("{}-", text)),
+        _ => Some(text),
+    }
+}
+
+/// The notes and tempo changes of a part
+struct Part {
+    notes: Vec<ScoreNote>,
+    tempos: Vec<(f64, f64)>,
+}
+
+fn read_part(part: &Element) -> Result<Part> {
+    let mut notes: Vec<ScoreNote> = Vec::new();
+    let mut tempos: Vec<(f64, f64)> = Vec::new();
+    // notes awaiting the continuation of a tie, by MIDI note
+    let mut ties: HashMap<usize, usize> = HashMap::new();
+
+    let mut divisions = 1.0;
+    let mut velocity = DEFAULT_VELOCITY;
+    let mut position = 0.0;
+    let mut chord_start = 0.0;
+
+    for measure in part.children("measure") {
+        for element in &measure.children {
+            match element.name.as_str() {
+                "attributes" => {
+                    if let Some(value) = element.child_number("divisions") {
+                        if !(value.is_finite() && value > 0.0) {
+                            return Err(invalid_data(format!("invalid divisions {}", value)));
+                        }
+                        divisions = value;
+                    }
+                }
+
+                "direction" => {
+                    if let Some(tempo) = direction_tempo(element) {
+                        tempos.push((position, tempo));
+                    }
+                    if let Some(value) = direction_velocity(element) {
+                        velocity = value;
+                    }
+                }
+
+                "sound" => {
+                    if let Some(tempo) = sound_tempo(element) {
+                        tempos.push((position, tempo));
+                    }
+                    if let Some(value) = sound_velocity(element) {
+                        velocity = value;
+                    }
+                }
+
+                "backup" => {
+                    position -= read_duration(element, divisions)?;
+                    position = position.max(0.0);
+                }
+
+                "forward" => {
+                    position += read_duration(element, divisions)?;
+                }
+
+                "note" => {
+                    // grace notes take no time of their own
+                    if element.has("grace") {
+                        continue;
+                    }
+
+                    let duration = read_duration(element, divisions)?;
+                    let start = if element.has("chord") {
+                        chord_start
+                    } else {
+                        chord_start = position;
+                        position += duration;
+                        chord_start
+                    };
+
+                    // rests, unpitched percussion and cue notes do not sound
+                    let pitch = match element.child("pitch") {
+                        Some(pitch) if !element.has("cue") => pitch,
+                        _ => continue,
+                    };
+                    let note = pitch_note(pitch)?;
+
+                    let tie_types: Vec<&str> = element
+                        .children("tie")
+                        .filter_map(|tie| tie.attribute("type"))
+                        .collect();
+                    let tie_stop = tie_types.contains(&"stop");
+                    let tie_start = tie_types.contains(&"start");
+
+                    let index = match ties.remove(&note) {
+                        // a tied note extends the note it continues
+                        Some(index) if tie_stop => {
+                            notes[index].end = start + duration;
+                            index
+                        }
+                        _ => {
+                            notes.push(ScoreNote {
+                                note,
+                                velocity: element
+                                    .attribute("dynamics")
+                                    .and_then(percent_velocity)
+                                    .unwrap_or(velocity),
+                                start,
+                                end: start + duration,
+                                syllable: note_syllable(element),
+                            });
+                            notes.len() - 1
+                        }
+                    };
+                    if tie_start {
+                        ties.insert(note, index);
+                    }
+                }
+
+                _ => {}
+            }
+        }
+    }
+
+    Ok(Part { notes, tempos })
+}
+
+/// Maps positions in quarter notes to MIDI ticks at the initial tempo, so tempo changes are
+/// kept by a song of a single tempo
+struct TempoMap {
+    /// Tempo changes as (position in quarter notes, tick, quarter notes per minute)
+    changes: Vec<(f64, f64, f64)>,
+}
+
+impl TempoMap {
+    fn new(mut tempos: Vec<(f64, f64)>) -> TempoMap {
+        tempos.retain(|&(_, bpm)| bpm > 0.0 && bpm.is_finite());
+        tempos.sort_by(|a, b| a.0.total_cmp(&b.0));
+        if tempos.first().is_none_or(|&(position, _)| position > 0.0) {
+            tempos.insert(0, (0.0, DEFAULT_BPM));
+        }
+
+        let initial_bpm = tempos[0].1;
+        let mut changes: Vec<(f64, f64, f64)> = Vec::with_capacity(tempos.len());
+        for (position, bpm) in tempos {
+            let tick = match changes.last() {
+                Some(&(previous, tick, previous_bpm)) => {
+                    tick + (position - previous) * TICKS_PER_QUARTER as f64 * initial_bpm
+                        / previous_bpm
+                }
+                None => 0.0,
+            };
+            changes.push((position, tick, bpm));
+        }
+
+        TempoMap { changes }
+    }
+
+    fn initial_bpm(&self) -> f64 {
+        self.changes[0].2
+    }
+
+    fn tick(&self, position: f64) -> usize {
+        let &(start, tick, bpm) = self
+            .changes
+            .iter()
+            .rev()
+            .find(|&&(start, _, _)| start <= position)
+            .unwrap_or(&self.changes[0]);
+        let tick = tick + (position - start) * TICKS_PER_QUARTER as f64 * self.initial_bpm() / bpm;
+        tick.round().max(0.0) as usize
+    }
+}
+
+/// Converts the notes of a part into a MIDI track, with their syllables as lyric events
+fn make_track(notes: &[ScoreNote], channel: u8, tempo_map: &TempoMap) -> MidiTrack {
+    let event = |event_type: EventType, time: usize, value1: usize, value2: usize| MidiEvent {
+        event_type,
+        system_event_type: None,
+        meta_event_type: None,
+        time,
+        channel,
+        value1,
+        value2: Some(value2),
+        text: None,
+    };
+
+    // (time, order at the same time, event): notes end before lyrics, which precede notes
+    let mut events: Vec<(usize, u8, MidiEvent)> = Vec::new();
+    for note in notes {
+        let start = tempo_map.tick(note.start);
+        let end = tempo_map.tick(note.end);
+        if end <= start {
+            continue;
+        }
+
+        if let Some(syllable) = &note.syllable {
+            let lyric = MidiEvent {
+                event_type: EventType::System,
+                system_event_type: Some(SystemEventType::SystemResetOrMeta),
+                meta_event_type: Some(MetaEventType::LyricText),
+                time: start,
+                channel,
+                value1: syllable.len(),
+                value2: None,
+                text: Some(syllable.clone()),
+            };
+            events.push((start, 1, lyric));
+        }
+        let velocity = note.velocity.clamp(1, 127);
+        events.push((
+            start,
+            2,
+            event(EventType::NoteOn, start, note.note, velocity),
+        ));
+        events.push((end, 0, event(EventType::NoteOff, end, note.note, 0)));
+    }
+    events.sort_by_key(|&(time, order, _)| (time, order));
+
+    let events: Vec<MidiEvent> = events.into_iter().map(|(_, _, event)| event).collect();
+    MidiTrack {
+        max_time: events.last().map_or(0, |event| event.time),
+        events,
+    }
+}
+
+/// Convenience method for parsing a `Result<MidiSong>` from a MusicXML filepath.
+/// ```no_run
+/// use serialization::musicxml::read_musicxml_file;
+///
+/// let song = read_musicxml_file("score.musicxml");
+/// ```
+pub fn read_musicxml_file<P: AsRef<Path>>(path: P) -> Result<MidiSong> {
+    let file = File::open(path)?;
+    let mut reader = BufReader::new(file);
+
+    read_musicxml(&mut reader)
+}
+
+/// Parses a partwise MusicXML score into a `Result<MidiSong>`, with a track per part.
+///
+/// Tied notes are merged, dynamics set note velocities, and tempo changes are folded into the
+/// note times, so `song.bpm` is the initial tempo. The syllables of the first lyric line become
+/// lyric events, so `MidiSong::notes` attaches them to their notes; notes under an extender or
+/// slur carry no syllable of their own.
+///
+/// ```
+/// use serialization::musicxml::read_musicxml;
+///
+/// let score = r#"<score-partwise><part id="P1"><measure number="1">
+///     <note><pitch><step>A</step><octave>4</octave></pitch><duration>1</duration>
+///     <lyric><text>La</text></lyric></note>
+/// </measure></part></score-partwise>"#;
+/// let song = read_musicxml(&mut score.as_bytes()).unwrap();
+///
+/// let notes = song.notes(None);
+/// assert_eq!(notes[0].note, 69);
+/// assert_eq!(notes[0].syllable.as_deref(), Some("La"));
+/// ```
+pub fn read_musicxml<T>(reader: &mut T) -> Result<MidiSong>
+where
+    T: Read,
+{
+    let mut source = String::new();
+    reader.read_to_string(&mut source)?;
+    let root = XmlParser::new(&source).parse_document()?;
+
+    if root.name != "score-partwise" {
+        return Err(invalid_data(format!(
+            "unsupported MusicXML root element `{}`",
+            root.name
+        )));
+    }
+
+    let parts = root
+        .children("part")
+        .map(read_part)
+        .collect::<Result<Vec<_>>>()?;
+    let tempo_map = TempoMap::new(
+        parts
+            .iter()
+            .flat_map(|part| part.tempos.iter().copied())
+            .collect(),
+    );
+
+    let tracks: Vec<MidiTrack> = parts
+        .iter()
+        .enumerate()
+        .map(|(i, part)| make_track(&part.notes, i.min(15) as u8, &tempo_map))
+        .collect();
+
+    Ok(MidiSong {
+        max_time: tracks.iter().map(|track| track.max_time).max().unwrap_or(0),
+        time_unit: TICKS_PER_QUARTER as isize,
+        track_count: tracks.len(),
+        tracks,
+        bpm: tempo_map.initial_bpm(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCORE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE score-partwise PUBLIC "-//Recordare//DTD MusicXML 4.0 Partwise//EN"
+  "http://www.musicxml.org/dtds/partwise.dtd">
+<score-partwise version="4.0">
+  <part-list><score-part id="P1"><part-name>Voice</part-name></score-part></part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>2</divisions></attributes>
+      <direction>
+        <direction-type><dynamics><p/></dynamics></direction-type>
+        <sound tempo="120"/>
+      </direction>
+      <note>
+        <pitch><step>C</step><octave>4</octave></pitch><duration>2</duration>
+        <lyric number="1"><syllabic>begin</syllabic><text>Hal</text></lyric>
+      </note>
+      <note>
+        <pitch><step>D</step><octave>4</octave></pitch><duration>2</duration>
+        <lyric number="1"><syllabic>middle</syllabic><text>le</text><extend/></lyric>
+      </note>
+      <!-- melisma -->
+      <note>
+        <pitch><step>E</step><octave>4</octave></pitch><duration>2</duration>
+        <lyric number="1"><extend type="stop"/></lyric>
+      </note>
+      <note>
+        <pitch><step>F</step><alter>1</alter><octave>4</octave></pitch><duration>2</duration>
+        <tie type="start"/>
+        <lyric number="1"><syllabic>end</syllabic><text>lu</text></lyric>
+      </note>
+    </measure>
+    <measure number="2">
+      <direction>
+        <direction-type>
+          <metronome><beat-unit>half</beat-unit><per-minute>30</per-minute></metronome>
+        </direction-type>
+      </direction>
+      <direction><direction-type><dynamics><f/></dynamics></direction-type></direction>
+      <note>
+        <pitch><step>F</step><alter>1</alter><octave>4</octave></pitch><duration>2</duration>
+        <tie type="stop"/>
+      </note>
+      <note><rest/><duration>2</duration></note>
+      <note>
+        <pitch><step>G</step><octave>4</octave></pitch><duration>4</duration>
+        <lyric number="1"><syllabic>single</syllabic><text>J&amp;a</text></lyric>
+      </note>
+      <note>
+        <chord/><pitch><step>B</step><octave>4</octave></pitch><duration>4</duration>
+      </note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+    #[test]
+    fn it_parses_xml() {
+        let root = XmlParser::new("<a x='1 &lt; 2'>b&#233;&#x26;<![CDATA[<c>]]><d/></a>")
+            .parse_document()
+            .expect("failed");
+        assert_eq!(root.attribute("x"), Some("1 < 2"));
+        assert_eq!(root.text, "bé&<c>");
+        assert!(root.has("d"));
+
+        assert!(XmlParser::new("<a><b></a>").parse_document().is_err());
+        assert!(XmlParser::new("<a>").parse_document().is_err());
+    }
+
+    #[test]
+    fn it_parses_a_musicxml_score() {
+        let song = read_musicxml(&mut SCORE.as_bytes()).expect("failed");
+        assert_eq!(song.tracks.len(), 1);
+        assert_eq!(song.time_unit, 960);
+        assert_eq!(song.bpm as usize, 120);
+        // the second measure is played at half the tempo
+        assert_eq!(song.max_time, 11520);
+
+        let notes = song.notes(None);
+        let summary: Vec<(usize, usize, usize, usize)> = notes
+            .iter()
+            .map(|note| (note.note, note.velocity, note.start, note.end))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (60, 49, 0, 960),
+                (62, 49, 960, 1920),
+                (64, 49, 1920, 2880),
+                (66, 49, 2880, 5760), // tied
+                (67, 96, 7680, 11520),
+                (71, 96, 7680, 11520),
+            ]
+        );
+
+        let syllables: Vec<Option<&str>> =
+            notes.iter().map(|note| note.syllable.as_deref()).collect();
+        assert_eq!(
+            syllables,
+            vec![
+                Some("Hal-"),
+                Some("le-"),
+                None,
+                Some("lu"),
+                Some("J&a"),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn it_rejects_timewise_scores() {
+        let score = "<score-timewise><measure number=\"1\"/></score-timewise>";
+        assert!(read_musicxml(&mut score.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn it_rejects_invalid_durations() {
+        for duration in ["NaN", "inf", "-1"] {
+            let score = format!(
+                "<score-partwise><part id=\"P1\"><measure number=\"1\"><note>\
+                 <pitch><step>A</step><octave>4</octave></pitch><duration>{}</duration>\
+                 </note></measure></part></score-partwise>",
+                duration
+            );
+            let error = read_musicxml(&mut score.as_bytes()).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidData);
+        }
+    }
+}
@@ -284,7 +284,7 @@ where
 
     for track in &song.tracks {
         for i in 0..track.events.len() {
-            let event = track.events[i];
+            let event = &track.events[i];
             if event.event_type == midi::EventType::NoteOn {
                 let start_tick = event.time;
                 let note = event.value1;
@@ -292,7 +292,7 @@ where
 
                 let mut end_tick = song.max_time;
                 for j in i..track.events.len() {
-                    let event_cursor = track.events[j];
+                    let event_cursor = &track.events[j];
 
                     if event_cursor.value1 == note && event_cursor.is_note_terminating() {
                         end_tick = event_cursor.time;
@@ -1,34 +1,38 @@
-use core::filter::stateful::tract::Tract;
-use core::source::Glottis;
+use core::filter::stateful::tract::BLOCK_SIZE;
+use core::voice::Voice;
 use rodio::{OutputStream, Source};
 use std::thread::sleep;
 use std::time::Duration;
 
 // mod stream;
-struct Voice {
-    i: usize,
-    sample_rate: usize,
-    glottis: Glottis,
-    tract: Tract,
+/// Streams a voice to rodio, rendering a block at a time.
+struct Player {
+    voice: Voice<f32>,
+    buffer: [f32; BLOCK_SIZE],
+    index: usize,
 }
 
-impl Iterator for Voice {
+impl Iterator for Player {
     type Item = f32;
 
     fn next(&mut self) -> Option<f32> {
-        let t = self.i as f64 / self.sample_rate as f64;
-        self.i += 1;
-        return Some(self.generate(t));
+        if self.index == BLOCK_SIZE {
+            self.voice.render_block(&mut self.buffer);
+            self.index = 0;
+        }
+        let sample = self.buffer[self.index];
+        self.index += 1;
+        return Some(sample);
     }
 }
 
-impl Source for Voice {
+impl Source for Player {
     fn channels(&self) -> u16 {
         return 1;
     }
 
     fn sample_rate(&self) -> u32 {
-        return self.sample_rate as u32;
+        return self.voice.sample_rate() as u32;
     }
 
     fn total_duration(&self) -> Option<Duration> {
@@ -40,43 +44,23 @@ impl Source for Voice {
     }
 }
 
-impl Voice {
-    pub fn new(sample_rate: usize) -> Voice {
-        // init glottis
-        let mut glottis = Glottis::new();
-        glottis.tenseness = 0.5;
-        glottis.pre_block();
-
-        // init tract
-        let mut tract = Tract::new(60, 32);
-        tract.init();
-
-        Voice {
-            i: 0,
-            sample_rate,
-            glottis,
-            tract,
+impl Player {
+    pub fn new(voice: Voice<f32>) -> Player {
+        Player {
+            voice,
+            buffer: [0.0; BLOCK_SIZE],
+            index: BLOCK_SIZE,
         }
     }
-
-    fn generate(&mut self, t: f64) -> f32 {
-        let glottal_output = self.glottis.tick(t);
-        let tract_output = self.tract.process(glottal_output, 0.0);
-        return tract_output as f32;
-        // return glottal_output as f32;
-    }
-
-    pub fn set_frequency(&mut self, value: f64) {
-        self.glottis.frequency = value;
-    }
 }
 
 fn main() {
-    let mut voice = Voice::new(44100);
-    voice.set_frequency(110.0);
+    let mut voice = Voice::new(44100.0);
+    voice.note_on(45, 100); // A2 (110hz)
 
+    let player = Player::new(voice);
     let (_stream, handle) = OutputStream::try_default().unwrap();
-    let _result = handle.play_raw(voice.convert_samples().fade_in(Duration::from_millis(100)));
+    let _result = handle.play_raw(player.convert_samples().fade_in(Duration::from_millis(100)));
 
     sleep(Duration::from_millis(3000));
 }
@@ -88,10 +72,12 @@ mod tests {
     #[test]
     // #[allow(clippy::float_cmp)]
     fn test_iterator() {
-        let mut voice = Voice::new(44100);
+        let mut voice = Voice::new(44100.0);
+        voice.note_on(45, 100);
+        let mut player = Player::new(voice);
 
         for n in 0..256 {
-            let next = voice.next();
+            let next = player.next();
             println!("{:?}", next);
             // assert_eq!(next, Some(0.0));
         }